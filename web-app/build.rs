@@ -1,129 +1,324 @@
+#[path = "build/header.rs"]
+mod header;
+
+use header::{is_rust_ident, Diagnostic, Example, HeaderParser};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::fs;
-use std::io::Write;
-use std::path::Path;
-
-struct Example {
-    id: String,
-    title: String,
-    description: String,
-    html: String,
-    file_path: String,
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+struct ExamplesRoot<'a> {
+    examples: &'a [Example],
+}
+
+fn example_files(examples_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(examples_dir)
+        .expect("Failed to read examples directory")
+        .map(|entry| entry.expect("Failed to read directory entry").path())
+        .filter(|path| {
+            path.extension().and_then(|s| s.to_str()) == Some("rs")
+                && path.file_name().and_then(|s| s.to_str()) != Some("mod.rs")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+const GENERATED_RUST: &str = r#"// Auto-generated file - DO NOT EDIT
+pub const EXAMPLES_DATA: &str = include_str!("examples_data.yaml");
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleSnippet {
+    pub name: String,
+    pub language: String,
+    pub code: String,
+}
+
+/// How an example card presents itself in the gallery.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExampleLayout {
+    /// Title and description only; the demo is shown once the card is opened.
+    #[default]
+    Card,
+    /// The live demo is rendered on the card front as well.
+    Inline,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleRoute {
+    pub method: String,
+    pub path: String,
+    pub handler: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleData {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub order: i32,
+    pub pinned: bool,
+    pub layout: ExampleLayout,
+    pub language: String,
+    pub html: String,
+    pub snippets: Vec<ExampleSnippet>,
+    pub routes: Vec<ExampleRoute>,
+    pub backend_file: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExamplesRoot {
+    pub examples: Vec<ExampleData>,
 }
 
-fn extract_metadata(file_content: &str, file_path: &str) -> Option<Example> {
-    let mut title = String::new();
-    let mut description = String::new();
-    let mut html_lines = Vec::new();
-    let mut in_html = false;
-    
-    for line in file_content.lines() {
-        if line.starts_with("//! @title") {
-            title = line.trim_start_matches("//! @title").trim().to_string();
-        } else if line.starts_with("//! @description") {
-            description = line.trim_start_matches("//! @description").trim().to_string();
-        } else if line.starts_with("//! @html_start") {
-            in_html = true;
-        } else if line.starts_with("//! @html_end") {
-            in_html = false;
-        } else if in_html && line.starts_with("//!") {
-            let content = line.strip_prefix("//!").unwrap_or("");
-            // Only remove a single leading space if it exists (for the comment formatting)
-            let content = if content.starts_with(' ') {
-                &content[1..]
-            } else {
-                content
-            };
-            html_lines.push(content);
+pub fn get_examples() -> Vec<ExampleData> {
+    let examples_root: ExamplesRoot = serde_yaml_ng::from_str(EXAMPLES_DATA)
+        .expect("Failed to parse examples data");
+    examples_root.examples
+}
+
+/// Backend source of an example with its `//!` header stripped.
+#[derive(Debug, Clone, Copy)]
+pub struct ExampleSource {
+    pub id: &'static str,
+    pub language: &'static str,
+    pub code: &'static str,
+}
+
+/// Looks up the embedded backend source for an example id.
+pub fn get_example_source(id: &str) -> Option<&'static ExampleSource> {
+    EXAMPLE_SOURCES.iter().find(|source| source.id == id)
+}
+"#;
+
+/// Location of the crawled Datastar docs, relative to this crate.
+const DOCS_DIR: &str = "../ref/datastar-docs";
+
+/// Embeds every markdown page listed in the docs `index.json`, in index order.
+///
+/// A missing docs directory only produces a warning (the app then has no docs),
+/// but a page listed in the index that cannot be read fails the build.
+fn generate_docs() -> String {
+    let docs_dir = Path::new(DOCS_DIR);
+    let index_path = docs_dir.join("index.json");
+    let mut out = String::from(
+        "// Auto-generated file - DO NOT EDIT\n\n\
+         /// A markdown page from the bundled Datastar docs.\n\
+         #[derive(Debug, Clone, Copy)]\n\
+         pub struct EmbeddedDoc {\n    \
+             pub section: &'static str,\n    \
+             pub slug: &'static str,\n    \
+             pub markdown: &'static str,\n\
+         }\n\n\
+         pub static EMBEDDED_DOCS: &[EmbeddedDoc] = &[\n",
+    );
+
+    let Ok(index) = fs::read_to_string(&index_path) else {
+        println!("cargo:warning=no docs index at {}, docs are disabled", index_path.display());
+        out.push_str("];\n");
+        return out;
+    };
+    let index: serde_json::Value = serde_json::from_str(&index)
+        .unwrap_or_else(|e| panic!("{}: invalid JSON: {e}", index_path.display()));
+    let sections = index["sections"]
+        .as_object()
+        .unwrap_or_else(|| panic!("{}: missing `sections` object", index_path.display()));
+
+    for (section, pages) in sections {
+        let pages = pages
+            .as_array()
+            .unwrap_or_else(|| panic!("{}: section `{section}` is not a list", index_path.display()));
+        for page in pages {
+            let file = page
+                .as_str()
+                .unwrap_or_else(|| panic!("{}: `{section}` lists a non-string page", index_path.display()));
+            let path = docs_dir.join(section).join(file);
+            if !path.is_file() {
+                panic!("{}: listed page {} does not exist", index_path.display(), path.display());
+            }
+            let slug = file.strip_suffix(".md").unwrap_or(file);
+            out.push_str(&format!(
+                "    EmbeddedDoc {{\n        section: \"{section}\",\n        slug: \"{slug}\",\n        markdown: include_str!(\"../{DOCS_DIR}/{section}/{file}\"),\n    }},\n"
+            ));
         }
     }
-    
-    if title.is_empty() || description.is_empty() {
-        return None;
-    }
-    
-    let id = Path::new(file_path)
-        .file_stem()?
-        .to_str()?
-        .to_string()
-        .replace('_', "-");
-    
-    Some(Example {
-        id,
-        title,
-        description,
-        html: html_lines.join("\n"),
-        file_path: file_path.to_string(),
-    })
+    out.push_str("];\n");
+    out
 }
 
 fn main() {
     println!("cargo:rerun-if-changed=src/examples");
-    
+    println!("cargo:rerun-if-changed={DOCS_DIR}");
+
     let examples_dir = Path::new("src/examples");
     let mut examples = Vec::new();
-    
+    let mut diagnostics = Vec::new();
+
     if examples_dir.exists() {
-        for entry in fs::read_dir(examples_dir).expect("Failed to read examples directory") {
-            let entry = entry.expect("Failed to read directory entry");
-            let path = entry.path();
-            
-            if path.extension().and_then(|s| s.to_str()) == Some("rs") && 
-               path.file_name().and_then(|s| s.to_str()) != Some("mod.rs") {
-                
-                let content = fs::read_to_string(&path)
-                    .expect(&format!("Failed to read file: {:?}", path));
-                
-                if let Some(example) = extract_metadata(&content, path.to_str().unwrap()) {
-                    examples.push(example);
-                }
+        for path in example_files(examples_dir) {
+            let file = path.to_str().expect("Example path is not valid UTF-8");
+            let content = fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("Failed to read file {file}: {e}"));
+            let module = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .expect("Example file name is not valid UTF-8");
+            if !is_rust_ident(module) {
+                diagnostics.push(Diagnostic {
+                    file: file.to_string(),
+                    line: 1,
+                    message: format!("file name `{module}` is not a valid module name"),
+                });
+                continue;
+            }
+
+            match HeaderParser::new(file).parse(&content, module) {
+                Ok(example) => examples.push(example),
+                Err(errors) => diagnostics.extend(errors),
             }
         }
     }
-    
-    // Generate YAML data file
-    let yaml_path = Path::new("generated/examples_data.yaml");
-    fs::create_dir_all("generated").expect("Failed to create generated directory");
-    
-    let mut yaml_file = fs::File::create(yaml_path).expect("Failed to create YAML file");
-    writeln!(yaml_file, "examples:").expect("Failed to write to YAML file");
-    
+
+    let mut seen_ids = HashSet::new();
+    for example in &examples {
+        if !seen_ids.insert(example.id.as_str()) {
+            diagnostics.push(Diagnostic {
+                file: example.backend_file.clone(),
+                line: 1,
+                message: format!("duplicate example id `{}`", example.id),
+            });
+        }
+    }
+
+    let mut seen_routes: Vec<(&str, &str, &str)> = Vec::new();
     for example in &examples {
-        writeln!(yaml_file, "  - id: \"{}\"", example.id).expect("Failed to write to YAML");
-        writeln!(yaml_file, "    title: \"{}\"", example.title).expect("Failed to write to YAML");
-        writeln!(yaml_file, "    description: \"{}\"", example.description).expect("Failed to write to YAML");
-        writeln!(yaml_file, "    html: |").expect("Failed to write to YAML");
-        for line in example.html.lines() {
-            writeln!(yaml_file, "      {}", line).expect("Failed to write to YAML");
+        for route in &example.routes {
+            let owner = seen_routes
+                .iter()
+                .find(|(method, path, _)| *method == route.method && *path == route.path);
+            match owner {
+                Some((_, _, owner)) => diagnostics.push(Diagnostic {
+                    file: example.backend_file.clone(),
+                    line: route.line,
+                    message: format!(
+                        "route `{} {}` is already declared by {owner}",
+                        route.method, route.path
+                    ),
+                }),
+                None => seen_routes.push((&route.method, &route.path, &example.backend_file)),
+            }
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        for diagnostic in &diagnostics {
+            eprintln!("error: {diagnostic}");
+        }
+        eprintln!(
+            "error: {} malformed example header(s) in {}",
+            diagnostics.len(),
+            examples_dir.display()
+        );
+        std::process::exit(1);
+    }
+
+    examples.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.id.cmp(&b.id)));
+
+    fs::create_dir_all("generated").expect("Failed to create generated directory");
+
+    let yaml = serde_yaml_ng::to_string(&ExamplesRoot { examples: &examples })
+        .expect("Failed to serialize examples data");
+    write_if_changed(Path::new("generated/examples_data.yaml"), &yaml);
+    write_if_changed(
+        Path::new("generated/examples_data.rs"),
+        &format!("{GENERATED_RUST}{}", generate_sources(&examples)),
+    );
+    write_if_changed(
+        Path::new("generated/examples_routes.rs"),
+        &generate_routes(&examples),
+    );
+    write_if_changed(Path::new("generated/docs_data.rs"), &generate_docs());
+}
+
+/// Embeds each example's stripped source as a raw string literal keyed by id.
+fn generate_sources(examples: &[Example]) -> String {
+    let mut out = String::from("\npub static EXAMPLE_SOURCES: &[ExampleSource] = &[\n");
+    for example in examples {
+        // Use one more `#` than the longest `"#...` run in the source.
+        let mut hashes = 1;
+        while example.source.contains(&format!("\"{}", "#".repeat(hashes))) {
+            hashes += 1;
+        }
+        let fence = "#".repeat(hashes);
+        out.push_str(&format!(
+            "    ExampleSource {{\n        id: \"{}\",\n        language: \"{}\",\n        code: r{fence}\"{}\"{fence},\n    }},\n",
+            example.id, example.language, example.source
+        ));
+    }
+    out.push_str("];\n");
+    out
+}
+
+/// Declares one module per example file and a `register_examples` function that
+/// mounts every `@route` on the application router.
+fn generate_routes(examples: &[Example]) -> String {
+    let mut modules: Vec<&Example> = examples.iter().collect();
+    modules.sort_by(|a, b| a.module.cmp(&b.module));
+
+    let mut out = String::from("// Auto-generated file - DO NOT EDIT\n");
+    out.push_str("use crate::{hypermedia::fragment_only, AppState};\nuse axum::{routing, Router};\n\n");
+    for example in &modules {
+        out.push_str(&format!(
+            "#[path = \"../{}\"]\npub mod {};\n",
+            example.backend_file, example.module
+        ));
+    }
+
+    // Group methods by path so `GET` and `POST` on one path share a `.route(...)`.
+    // Example endpoints only serve Datastar actions; anything else is sent to
+    // the page of the example that declared the path first.
+    let mut paths: Vec<(&str, &str, Vec<String>)> = Vec::new();
+    for example in examples {
+        for route in &example.routes {
+            let handler = format!(
+                "routing::{}({}::{})",
+                route.method.to_lowercase(),
+                example.module,
+                route.handler
+            );
+            match paths.iter_mut().find(|(path, _, _)| *path == route.path) {
+                Some((_, _, handlers)) => handlers.push(handler),
+                None => paths.push((&route.path, &example.id, vec![handler])),
+            }
+        }
+    }
+
+    out.push_str("\n/// Mounts the endpoints declared with `@route` in each example header.\n");
+    out.push_str("pub fn register_examples(router: Router<AppState>) -> Router<AppState> {\n    router");
+    for (path, owner, handlers) in &paths {
+        let mut method_router = handlers[0].clone();
+        for handler in &handlers[1..] {
+            // `routing::post(x)` chains as `.post(x)` on the method router.
+            method_router.push_str(&format!(".{}", handler.trim_start_matches("routing::")));
         }
-        writeln!(yaml_file, "    backend_file: \"{}\"", example.file_path).expect("Failed to write to YAML");
+        out.push_str(&format!(
+            "\n        .route(\n            \"{path}\",\n            {method_router}.route_layer(fragment_only(\"/examples/{owner}\")),\n        )"
+        ));
     }
-    
-    // Generate Rust code to include the examples data
-    let rust_path = Path::new("generated/examples_data.rs");
-    let mut rust_file = fs::File::create(rust_path).expect("Failed to create Rust file");
-    
-    writeln!(rust_file, "// Auto-generated file - DO NOT EDIT").expect("Failed to write");
-    writeln!(rust_file, "pub const EXAMPLES_DATA: &str = include_str!(\"examples_data.yaml\");").expect("Failed to write");
-    writeln!(rust_file, "").expect("Failed to write");
-    writeln!(rust_file, "use serde::{{Deserialize, Serialize}};").expect("Failed to write");
-    writeln!(rust_file, "").expect("Failed to write");
-    writeln!(rust_file, "#[derive(Debug, Clone, Serialize, Deserialize)]").expect("Failed to write");
-    writeln!(rust_file, "pub struct ExampleData {{").expect("Failed to write");
-    writeln!(rust_file, "    pub id: String,").expect("Failed to write");
-    writeln!(rust_file, "    pub title: String,").expect("Failed to write");
-    writeln!(rust_file, "    pub description: String,").expect("Failed to write");
-    writeln!(rust_file, "    pub html: String,").expect("Failed to write");
-    writeln!(rust_file, "    pub backend_file: String,").expect("Failed to write");
-    writeln!(rust_file, "}}").expect("Failed to write");
-    writeln!(rust_file, "").expect("Failed to write");
-    writeln!(rust_file, "#[derive(Debug, Deserialize)]").expect("Failed to write");
-    writeln!(rust_file, "pub struct ExamplesRoot {{").expect("Failed to write");
-    writeln!(rust_file, "    pub examples: Vec<ExampleData>,").expect("Failed to write");
-    writeln!(rust_file, "}}").expect("Failed to write");
-    writeln!(rust_file, "").expect("Failed to write");
-    writeln!(rust_file, "pub fn get_examples() -> Vec<ExampleData> {{").expect("Failed to write");
-    writeln!(rust_file, "    let examples_root: ExamplesRoot = serde_yaml_ng::from_str(EXAMPLES_DATA)").expect("Failed to write");
-    writeln!(rust_file, "        .expect(\"Failed to parse examples data\");").expect("Failed to write");
-    writeln!(rust_file, "    examples_root.examples").expect("Failed to write");
-    writeln!(rust_file, "}}").expect("Failed to write");
-}
\ No newline at end of file
+    out.push_str("\n}\n");
+    out
+}
+
+/// Avoids touching generated files (and retriggering rebuilds) when nothing changed.
+fn write_if_changed(path: &Path, content: &str) {
+    if fs::read_to_string(path).ok().as_deref() != Some(content) {
+        fs::write(path, content)
+            .unwrap_or_else(|e| panic!("Failed to write {}: {e}", path.display()));
+    }
+}
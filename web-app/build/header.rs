@@ -0,0 +1,472 @@
+use serde::Serialize;
+use std::fmt;
+
+/// A single named code block declared with `@snippet <name> [language]` ... `@end`.
+#[derive(Serialize)]
+pub struct Snippet {
+    pub name: String,
+    pub language: String,
+    pub code: String,
+}
+
+/// An endpoint declared with `@route <METHOD> <path> <handler>`.
+#[derive(Serialize)]
+pub struct Route {
+    pub method: String,
+    pub path: String,
+    pub handler: String,
+    #[serde(skip)]
+    pub line: usize,
+}
+
+#[derive(Serialize)]
+pub struct Example {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub order: i32,
+    pub pinned: bool,
+    pub layout: String,
+    pub language: String,
+    pub html: String,
+    pub snippets: Vec<Snippet>,
+    pub routes: Vec<Route>,
+    pub backend_file: String,
+    #[serde(skip)]
+    pub module: String,
+    /// The backend code without its `//!` header, embedded into the binary.
+    #[serde(skip)]
+    pub source: String,
+}
+
+/// A header problem pointing at the offending `file:line`.
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+/// Parses the leading `//!` header of an example file.
+///
+/// Every header line is either blank, a `@directive`, or a line inside an open
+/// `@snippet` block. Anything else is reported with its line number rather than
+/// being silently ignored.
+pub struct HeaderParser<'a> {
+    file: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> HeaderParser<'a> {
+    pub fn new(file: &'a str) -> Self {
+        Self {
+            file,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, line: usize, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            file: self.file.to_string(),
+            line,
+            message: message.into(),
+        });
+    }
+
+    pub fn parse(mut self, content: &str, module: &str) -> Result<Example, Vec<Diagnostic>> {
+        let mut title: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut category: Option<String> = None;
+        let mut tags: Option<Vec<String>> = None;
+        let mut order: Option<i32> = None;
+        let mut pinned: Option<bool> = None;
+        let mut layout: Option<String> = None;
+        let mut language: Option<String> = None;
+        let mut snippets: Vec<Snippet> = Vec::new();
+        let mut routes: Vec<Route> = Vec::new();
+        // (line the block was opened on, name, language, collected lines)
+        let mut open_snippet: Option<(usize, String, String, Vec<String>)> = None;
+        let mut header_lines = 0;
+        let source = content
+            .lines()
+            .skip_while(|line| line.starts_with("//!"))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+
+        for (index, raw) in content.lines().enumerate() {
+            let line_no = index + 1;
+            let Some(rest) = raw.strip_prefix("//!") else {
+                break;
+            };
+            header_lines += 1;
+            // Only remove a single leading space (the comment formatting) so
+            // snippet indentation is preserved.
+            let text = rest.strip_prefix(' ').unwrap_or(rest);
+
+            if let Some((_, _, _, lines)) = open_snippet.as_mut() {
+                if text.trim() == "@end" {
+                    let (_, name, language, lines) = open_snippet.take().unwrap();
+                    snippets.push(Snippet {
+                        name,
+                        language,
+                        code: lines.join("\n"),
+                    });
+                } else {
+                    lines.push(text.trim_end().to_string());
+                }
+                continue;
+            }
+
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let Some(directive) = text.strip_prefix('@') else {
+                self.error(line_no, format!("expected an `@directive`, found `{text}`"));
+                continue;
+            };
+            let (name, value) = match directive.split_once(char::is_whitespace) {
+                Some((name, value)) => (name, value.trim()),
+                None => (directive, ""),
+            };
+
+            match name {
+                "title" => self.set_text(&mut title, "title", value, line_no),
+                "description" => self.set_text(&mut description, "description", value, line_no),
+                "category" => {
+                    if is_identifier(value) {
+                        self.set(&mut category, "category", value.to_string(), line_no);
+                    } else {
+                        self.error(
+                            line_no,
+                            format!("invalid category `{value}`: categories are lowercase words joined by `-`"),
+                        );
+                    }
+                }
+                "tags" => {
+                    let parsed = self.parse_tags(value, line_no);
+                    self.set(&mut tags, "tags", parsed, line_no);
+                }
+                "order" => match value.parse::<i32>() {
+                    Ok(parsed) => self.set(&mut order, "order", parsed, line_no),
+                    Err(_) => self.error(
+                        line_no,
+                        format!("`@order` expects an integer, found `{value}`"),
+                    ),
+                },
+                "pinned" => {
+                    let parsed = match value {
+                        "" | "true" => Some(true),
+                        "false" => Some(false),
+                        _ => None,
+                    };
+                    match parsed {
+                        Some(parsed) => self.set(&mut pinned, "pinned", parsed, line_no),
+                        None => self.error(
+                            line_no,
+                            format!("`@pinned` expects `true`, `false` or nothing, found `{value}`"),
+                        ),
+                    }
+                }
+                "layout" => {
+                    if LAYOUTS.contains(&value) {
+                        self.set(&mut layout, "layout", value.to_string(), line_no);
+                    } else {
+                        self.error(
+                            line_no,
+                            format!("unknown layout `{value}`, expected one of {}", LAYOUTS.join(", ")),
+                        );
+                    }
+                }
+                "language" => {
+                    if is_identifier(value) {
+                        self.set(&mut language, "language", value.to_string(), line_no);
+                    } else {
+                        self.error(line_no, format!("`@language` expects a language name, found `{value}`"));
+                    }
+                }
+                "snippet" => {
+                    let mut parts = value.split_whitespace();
+                    let snippet_name = parts.next().unwrap_or_default();
+                    let snippet_language = parts.next().unwrap_or(snippet_name);
+                    if !is_identifier(snippet_name) {
+                        self.error(line_no, "`@snippet` expects a name, e.g. `@snippet html`");
+                    } else if parts.next().is_some() {
+                        self.error(line_no, "`@snippet` takes a name and an optional language");
+                    } else if snippets.iter().any(|s| s.name == snippet_name) {
+                        self.error(line_no, format!("duplicate snippet `{snippet_name}`"));
+                    }
+                    open_snippet = Some((
+                        line_no,
+                        snippet_name.to_string(),
+                        snippet_language.to_string(),
+                        Vec::new(),
+                    ));
+                }
+                "route" => {
+                    if let Some(route) = self.parse_route(value, line_no) {
+                        if routes
+                            .iter()
+                            .any(|r| r.method == route.method && r.path == route.path)
+                        {
+                            self.error(
+                                line_no,
+                                format!("duplicate route `{} {}`", route.method, route.path),
+                            );
+                        } else {
+                            routes.push(route);
+                        }
+                    }
+                }
+                "end" => self.error(line_no, "`@end` without a matching `@snippet`"),
+                other => self.error(line_no, format!("unknown directive `@{other}`")),
+            }
+        }
+
+        if let Some((opened_at, name, language, lines)) = open_snippet {
+            self.error(opened_at, format!("snippet `{name}` is never closed with `@end`"));
+            snippets.push(Snippet {
+                name,
+                language,
+                code: lines.join("\n"),
+            });
+        }
+        let header_end = header_lines.max(1);
+        if title.is_none() {
+            self.error(header_end, "missing required `@title`");
+        }
+        if description.is_none() {
+            self.error(header_end, "missing required `@description`");
+        }
+        if category.is_none() {
+            self.error(header_end, "missing required `@category`");
+        }
+        let html = snippets.iter().find(|s| s.name == "html").map(|s| s.code.clone());
+        if html.is_none() {
+            self.error(header_end, "missing required `@snippet html` block");
+        }
+
+        if !self.diagnostics.is_empty() {
+            return Err(self.diagnostics);
+        }
+
+        Ok(Example {
+            id: module.replace('_', "-"),
+            title: title.unwrap_or_default(),
+            description: description.unwrap_or_default(),
+            category: category.unwrap_or_default(),
+            tags: tags.unwrap_or_default(),
+            order: order.unwrap_or_default(),
+            pinned: pinned.unwrap_or_default(),
+            layout: layout.unwrap_or_else(|| LAYOUTS[0].to_string()),
+            language: language.unwrap_or_else(|| "rust".to_string()),
+            html: html.unwrap_or_default(),
+            snippets,
+            routes,
+            backend_file: self.file.to_string(),
+            module: module.to_string(),
+            source,
+        })
+    }
+
+    fn set<T>(&mut self, slot: &mut Option<T>, name: &str, value: T, line: usize) {
+        if slot.is_some() {
+            self.error(line, format!("`@{name}` is declared more than once"));
+        } else {
+            *slot = Some(value);
+        }
+    }
+
+    fn set_text(&mut self, slot: &mut Option<String>, name: &str, value: &str, line: usize) {
+        if value.is_empty() {
+            self.error(line, format!("`@{name}` must not be empty"));
+        } else {
+            self.set(slot, name, value.to_string(), line);
+        }
+    }
+
+    fn parse_route(&mut self, value: &str, line: usize) -> Option<Route> {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let [method, path, handler] = parts[..] else {
+            self.error(line, "`@route` expects `<METHOD> <path> <handler>`");
+            return None;
+        };
+        if !ROUTE_METHODS.contains(&method) {
+            self.error(
+                line,
+                format!("unsupported method `{method}`, expected one of {}", ROUTE_METHODS.join(", ")),
+            );
+            return None;
+        }
+        if !path.starts_with('/') {
+            self.error(line, format!("route path `{path}` must start with `/`"));
+            return None;
+        }
+        if !is_rust_ident(handler) {
+            self.error(line, format!("`{handler}` is not a valid handler function name"));
+            return None;
+        }
+        Some(Route {
+            method: method.to_string(),
+            path: path.to_string(),
+            handler: handler.to_string(),
+            line,
+        })
+    }
+
+    fn parse_tags(&mut self, value: &str, line: usize) -> Vec<String> {
+        let mut tags = Vec::new();
+        for tag in value.split(',').map(str::trim) {
+            if !is_identifier(tag) {
+                self.error(
+                    line,
+                    format!("invalid tag `{tag}`: tags are lowercase words joined by `-`"),
+                );
+            } else if tags.iter().any(|t| t == tag) {
+                self.error(line, format!("duplicate tag `{tag}`"));
+            } else {
+                tags.push(tag.to_string());
+            }
+        }
+        tags
+    }
+}
+
+/// `card` shows only the title on the card front, `inline` also renders the live demo.
+const LAYOUTS: [&str; 2] = ["card", "inline"];
+
+const ROUTE_METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+pub fn is_rust_ident(value: &str) -> bool {
+    let mut chars = value.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_identifier(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "//! @title Demo
+//! @description Does a thing
+//! @category demos
+//! @tags sse, forms
+//! @route GET /examples/demo/load load
+//! @snippet html
+//! <div>
+//!     <p>Hi</p>
+//! </div>
+//! @end
+
+pub async fn load() {}
+";
+
+    /// The header with `lines` inserted before the `@snippet`.
+    fn with_lines(lines: &str) -> String {
+        VALID.replacen("//! @snippet", &format!("{lines}\n//! @snippet"), 1)
+    }
+
+    fn errors(content: &str) -> Vec<String> {
+        match HeaderParser::new("demo.rs").parse(content, "demo") {
+            Ok(_) => Vec::new(),
+            Err(diagnostics) => diagnostics.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn parses_a_valid_header() {
+        let example = HeaderParser::new("demo.rs")
+            .parse(VALID, "demo_page")
+            .unwrap_or_else(|errors| panic!("{}", errors[0]));
+        assert_eq!(example.id, "demo-page");
+        assert_eq!(example.module, "demo_page");
+        assert_eq!(example.tags, ["sse", "forms"]);
+        assert_eq!(example.html, "<div>\n    <p>Hi</p>\n</div>");
+        assert_eq!(example.source, "pub async fn load() {}");
+        assert_eq!(example.layout, "card");
+        let route = &example.routes[0];
+        assert_eq!(
+            (route.method.as_str(), route.path.as_str(), route.handler.as_str(), route.line),
+            ("GET", "/examples/demo/load", "load", 5)
+        );
+    }
+
+    #[test]
+    fn reports_unknown_directives() {
+        assert_eq!(
+            errors(&with_lines("//! @colour blue")),
+            ["demo.rs:6: unknown directive `@colour`"]
+        );
+        assert_eq!(
+            errors(&with_lines("//! just text")),
+            ["demo.rs:6: expected an `@directive`, found `just text`"]
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_snippets() {
+        let content = VALID.replace("//! @end\n", "");
+        assert_eq!(
+            errors(&content),
+            ["demo.rs:6: snippet `html` is never closed with `@end`"]
+        );
+        assert_eq!(
+            errors(&with_lines("//! @end")),
+            ["demo.rs:6: `@end` without a matching `@snippet`"]
+        );
+    }
+
+    #[test]
+    fn reports_bad_routes() {
+        for (route, error) in [
+            ("GET /x", "`@route` expects `<METHOD> <path> <handler>`"),
+            ("FETCH /x load", "unsupported method `FETCH`, expected one of GET, POST, PUT, PATCH, DELETE"),
+            ("GET x load", "route path `x` must start with `/`"),
+            ("GET /x load-it", "`load-it` is not a valid handler function name"),
+            ("GET /examples/demo/load other", "duplicate route `GET /examples/demo/load`"),
+        ] {
+            assert_eq!(
+                errors(&with_lines(&format!("//! @route {route}"))),
+                [format!("demo.rs:6: {error}")]
+            );
+        }
+    }
+
+    #[test]
+    fn reports_duplicate_keys() {
+        assert_eq!(
+            errors(&with_lines("//! @title Again")),
+            ["demo.rs:6: `@title` is declared more than once"]
+        );
+        assert_eq!(
+            errors(&with_lines("//! @order 1\n//! @order 2")),
+            ["demo.rs:7: `@order` is declared more than once"]
+        );
+        assert_eq!(
+            errors(&with_lines("//! @snippet html\n//! @end")),
+            ["demo.rs:8: duplicate snippet `html`"]
+        );
+    }
+
+    #[test]
+    fn reports_missing_required_directives() {
+        let content = VALID.replace("//! @category demos\n", "");
+        assert_eq!(errors(&content), ["demo.rs:9: missing required `@category`"]);
+    }
+}
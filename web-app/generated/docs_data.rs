@@ -0,0 +1,227 @@
+// Auto-generated file - DO NOT EDIT
+
+/// A markdown page from the bundled Datastar docs.
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedDoc {
+    pub section: &'static str,
+    pub slug: &'static str,
+    pub markdown: &'static str,
+}
+
+pub static EMBEDDED_DOCS: &[EmbeddedDoc] = &[
+    EmbeddedDoc {
+        section: "guide",
+        slug: "reactive_signals",
+        markdown: include_str!("../../ref/datastar-docs/guide/reactive_signals.md"),
+    },
+    EmbeddedDoc {
+        section: "guide",
+        slug: "datastar_expressions",
+        markdown: include_str!("../../ref/datastar-docs/guide/datastar_expressions.md"),
+    },
+    EmbeddedDoc {
+        section: "guide",
+        slug: "getting_started",
+        markdown: include_str!("../../ref/datastar-docs/guide/getting_started.md"),
+    },
+    EmbeddedDoc {
+        section: "guide",
+        slug: "index",
+        markdown: include_str!("../../ref/datastar-docs/guide/index.md"),
+    },
+    EmbeddedDoc {
+        section: "guide",
+        slug: "backend_requests",
+        markdown: include_str!("../../ref/datastar-docs/guide/backend_requests.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "bulk_update",
+        markdown: include_str!("../../ref/datastar-docs/examples/bulk_update.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "bad_apple",
+        markdown: include_str!("../../ref/datastar-docs/examples/bad_apple.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "progressive_load",
+        markdown: include_str!("../../ref/datastar-docs/examples/progressive_load.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "edit_row",
+        markdown: include_str!("../../ref/datastar-docs/examples/edit_row.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "click_to_edit",
+        markdown: include_str!("../../ref/datastar-docs/examples/click_to_edit.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "lazy_load",
+        markdown: include_str!("../../ref/datastar-docs/examples/lazy_load.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "form_data",
+        markdown: include_str!("../../ref/datastar-docs/examples/form_data.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "title_update",
+        markdown: include_str!("../../ref/datastar-docs/examples/title_update.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "file_upload",
+        markdown: include_str!("../../ref/datastar-docs/examples/file_upload.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "inline_validation",
+        markdown: include_str!("../../ref/datastar-docs/examples/inline_validation.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "progress_bar",
+        markdown: include_str!("../../ref/datastar-docs/examples/progress_bar.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "custom_event",
+        markdown: include_str!("../../ref/datastar-docs/examples/custom_event.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "svg_morphing",
+        markdown: include_str!("../../ref/datastar-docs/examples/svg_morphing.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "click_to_load",
+        markdown: include_str!("../../ref/datastar-docs/examples/click_to_load.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "index",
+        markdown: include_str!("../../ref/datastar-docs/examples/index.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "web_component",
+        markdown: include_str!("../../ref/datastar-docs/examples/web_component.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "animations",
+        markdown: include_str!("../../ref/datastar-docs/examples/animations.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "lazy_tabs",
+        markdown: include_str!("../../ref/datastar-docs/examples/lazy_tabs.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "on_signal_patch",
+        markdown: include_str!("../../ref/datastar-docs/examples/on_signal_patch.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "infinite_scroll",
+        markdown: include_str!("../../ref/datastar-docs/examples/infinite_scroll.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "dbmon",
+        markdown: include_str!("../../ref/datastar-docs/examples/dbmon.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "delete_row",
+        markdown: include_str!("../../ref/datastar-docs/examples/delete_row.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "templ_counter",
+        markdown: include_str!("../../ref/datastar-docs/examples/templ_counter.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "sortable",
+        markdown: include_str!("../../ref/datastar-docs/examples/sortable.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "active_search",
+        markdown: include_str!("../../ref/datastar-docs/examples/active_search.md"),
+    },
+    EmbeddedDoc {
+        section: "examples",
+        slug: "todomvc",
+        markdown: include_str!("../../ref/datastar-docs/examples/todomvc.md"),
+    },
+    EmbeddedDoc {
+        section: "how_tos",
+        slug: "poll_the_backend_at_regular_intervals",
+        markdown: include_str!("../../ref/datastar-docs/how_tos/poll_the_backend_at_regular_intervals.md"),
+    },
+    EmbeddedDoc {
+        section: "how_tos",
+        slug: "load_more_list_items",
+        markdown: include_str!("../../ref/datastar-docs/how_tos/load_more_list_items.md"),
+    },
+    EmbeddedDoc {
+        section: "how_tos",
+        slug: "index",
+        markdown: include_str!("../../ref/datastar-docs/how_tos/index.md"),
+    },
+    EmbeddedDoc {
+        section: "how_tos",
+        slug: "bind_keydown_events_to_specific_keys",
+        markdown: include_str!("../../ref/datastar-docs/how_tos/bind_keydown_events_to_specific_keys.md"),
+    },
+    EmbeddedDoc {
+        section: "how_tos",
+        slug: "redirect_the_page_from_the_backend",
+        markdown: include_str!("../../ref/datastar-docs/how_tos/redirect_the_page_from_the_backend.md"),
+    },
+    EmbeddedDoc {
+        section: "reference",
+        slug: "sse_events",
+        markdown: include_str!("../../ref/datastar-docs/reference/sse_events.md"),
+    },
+    EmbeddedDoc {
+        section: "reference",
+        slug: "datastar_pro",
+        markdown: include_str!("../../ref/datastar-docs/reference/datastar_pro.md"),
+    },
+    EmbeddedDoc {
+        section: "reference",
+        slug: "sdks",
+        markdown: include_str!("../../ref/datastar-docs/reference/sdks.md"),
+    },
+    EmbeddedDoc {
+        section: "reference",
+        slug: "attributes",
+        markdown: include_str!("../../ref/datastar-docs/reference/attributes.md"),
+    },
+    EmbeddedDoc {
+        section: "reference",
+        slug: "index",
+        markdown: include_str!("../../ref/datastar-docs/reference/index.md"),
+    },
+    EmbeddedDoc {
+        section: "reference",
+        slug: "actions",
+        markdown: include_str!("../../ref/datastar-docs/reference/actions.md"),
+    },
+    EmbeddedDoc {
+        section: "reference",
+        slug: "security",
+        markdown: include_str!("../../ref/datastar-docs/reference/security.md"),
+    },
+];
@@ -3,12 +3,45 @@ pub const EXAMPLES_DATA: &str = include_str!("examples_data.yaml");
 
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleSnippet {
+    pub name: String,
+    pub language: String,
+    pub code: String,
+}
+
+/// How an example card presents itself in the gallery.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExampleLayout {
+    /// Title and description only; the demo is shown once the card is opened.
+    #[default]
+    Card,
+    /// The live demo is rendered on the card front as well.
+    Inline,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleRoute {
+    pub method: String,
+    pub path: String,
+    pub handler: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExampleData {
     pub id: String,
     pub title: String,
     pub description: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub order: i32,
+    pub pinned: bool,
+    pub layout: ExampleLayout,
+    pub language: String,
     pub html: String,
+    pub snippets: Vec<ExampleSnippet>,
+    pub routes: Vec<ExampleRoute>,
     pub backend_file: String,
 }
 
@@ -22,3 +55,543 @@ pub fn get_examples() -> Vec<ExampleData> {
         .expect("Failed to parse examples data");
     examples_root.examples
 }
+
+/// Backend source of an example with its `//!` header stripped.
+#[derive(Debug, Clone, Copy)]
+pub struct ExampleSource {
+    pub id: &'static str,
+    pub language: &'static str,
+    pub code: &'static str,
+}
+
+/// Looks up the embedded backend source for an example id.
+pub fn get_example_source(id: &str) -> Option<&'static ExampleSource> {
+    EXAMPLE_SOURCES.iter().find(|source| source.id == id)
+}
+
+pub static EXAMPLE_SOURCES: &[ExampleSource] = &[
+    ExampleSource {
+        id: "active-search",
+        language: "rust",
+        code: r##"use crate::{
+    catalog::ExampleQuery,
+    hypermedia::{Patches, Signals, TemplatePatch},
+    search::{tokenize, SearchHit, SearchKind},
+    templates::{ExampleFacetsTemplate, ExampleSearchResultsTemplate, SearchResultsTemplate},
+};
+use axum::extract::State;
+use datastar::consts::ElementPatchMode;
+use serde::Deserialize;
+
+/// How many ranked hits are listed above the example cards.
+const MAX_HITS: usize = 8;
+
+/// Signals sent by the search input and the facet chips.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct SearchSignals {
+    pub search: String,
+    pub category: String,
+    pub tag: String,
+}
+
+/// Example ids in rank order, or `None` when the text has no searchable
+/// terms (e.g. a single letter) and every example should stay visible.
+fn ranked_ids(text: &str, hits: &[SearchHit]) -> Option<Vec<String>> {
+    (!tokenize(text).is_empty()).then(|| {
+        hits.iter()
+            .filter(|hit| hit.kind == SearchKind::Example)
+            .map(|hit| hit.key.clone())
+            .collect()
+    })
+}
+
+pub async fn search(
+    State(state): State<crate::AppState>,
+    Signals(signals): Signals<SearchSignals>,
+) -> Patches {
+    // Rank examples and docs, then combine the ranking with the selected facets
+    let text = signals.search.trim().to_string();
+    let hits = state.search.search(&text);
+    let query = ExampleQuery {
+        ranked_ids: ranked_ids(&text, &hits),
+        category: signals.category,
+        tag: signals.tag,
+    };
+
+    // Log the search request
+    tracing::info!(
+        search_query = %text,
+        category = %query.category,
+        tag = %query.tag,
+        "Search request received"
+    );
+
+    let results = state.examples.search(&query);
+    let facets = state.examples.facets(&query);
+
+    // Log the search results
+    tracing::info!(
+        results_count = results.len(),
+        hits_count = hits.len(),
+        "Returning search results"
+    );
+
+    // The cards are patched inside their container, the facet bar and the
+    // hit list are morphed by id
+    Patches::new()
+        .elements(
+            TemplatePatch::new(ExampleSearchResultsTemplate { examples: results })
+                .selector("#example-cards")
+                .mode(ElementPatchMode::Inner),
+        )
+        .elements(TemplatePatch::new(ExampleFacetsTemplate { facets }))
+        .elements(TemplatePatch::new(SearchResultsTemplate {
+            query: text,
+            total: hits.len(),
+            hits: hits.into_iter().take(MAX_HITS).collect(),
+        }))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_without_terms_keeps_every_example() {
+        assert_eq!(ranked_ids("", &[]), None);
+        assert_eq!(ranked_ids("a", &[]), None);
+        assert_eq!(ranked_ids(" - ? ", &[]), None);
+        assert_eq!(ranked_ids("sse", &[]), Some(Vec::new()));
+    }
+}"##,
+    },
+    ExampleSource {
+        id: "hypermedia-demo",
+        language: "rust",
+        code: r#"use crate::{error::AppError, hypermedia::TemplatePatch, templates::DataItemsTemplate, AppState};
+use axum::{extract::State, response::IntoResponse};
+
+pub async fn get_items(
+    State(_state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let template = DataItemsTemplate {
+        message: "Hello from Rust Web Starter!".to_string(),
+        timestamp: chrono::Utc::now(),
+        items: vec![
+            "Item 1".to_string(),
+            "Item 2".to_string(),
+            "Item 3".to_string(),
+        ],
+    };
+
+    Ok(TemplatePatch::new(template).view_transition())
+}"#,
+    },
+    ExampleSource {
+        id: "form-demo",
+        language: "rust",
+        code: r#"use crate::{
+    error::AppError,
+    extract::Form,
+    hypermedia::{Patches, SignalPatch, TemplatePatch},
+    templates::FormResponseTemplate,
+    AppState,
+};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct SubmitRequest {
+    pub name: String,
+    pub value: String,
+}
+
+/// `$form`, owned by the server: the client only submits.
+#[derive(Serialize)]
+struct FormSignals {
+    form: FormStatus,
+}
+
+#[derive(Serialize)]
+struct FormStatus {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// `$form.first`, the name of the first entry saved on this page. Later
+/// saves send it too, but only the first one sets it.
+#[derive(Serialize)]
+struct FirstSaved {
+    form: FirstSavedName,
+}
+
+#[derive(Serialize)]
+struct FirstSavedName {
+    first: String,
+}
+
+pub async fn submit_form(
+    State(_state): State<AppState>,
+    Form(payload): Form<SubmitRequest>,
+) -> Result<Response, AppError> {
+    if payload.name.trim().is_empty() {
+        let invalid = FormSignals {
+            form: FormStatus {
+                status: "invalid",
+                error: Some("Name cannot be empty".to_string()),
+            },
+        };
+        return Ok(SignalPatch::new(&invalid)?.into_response());
+    }
+
+    tracing::info!("Received submission: {} = {}", payload.name, payload.value);
+
+    let template = FormResponseTemplate {
+        message: format!("Successfully processed: {}", payload.name),
+    };
+    let saved = FormSignals {
+        form: FormStatus {
+            status: "saved",
+            error: None,
+        },
+    };
+    let first = FirstSaved {
+        form: FirstSavedName { first: payload.name },
+    };
+
+    Ok(Patches::new()
+        .elements(TemplatePatch::new(template))
+        .signals(SignalPatch::new(&saved)?.remove("form.error"))
+        .signals(SignalPatch::new(&first)?.only_if_missing())
+        .into_response())
+}"#,
+    },
+    ExampleSource {
+        id: "theme-switcher",
+        language: "rust",
+        code: r#"use crate::{
+    error::AppError,
+    extract::Query,
+    handlers::{custom_seed, page_theme_for},
+    hypermedia::{SignalPatch, TemplatePatch},
+    preferences::THEME_COOKIE,
+    theme::generator::CUSTOM,
+    viewport::ViewportContext,
+    templates::{ThemeOptionsTemplate, ThemeStylesTemplate},
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct ThemeQuery {
+    pub theme: String,
+}
+
+/// The server decides which theme is active; the radios only ask for one.
+#[derive(Serialize)]
+pub(crate) struct ThemeSignals<'a> {
+    pub theme: &'a str,
+}
+
+/// The radios: `auto`, then one per theme in `config/themes`, then the
+/// visitor's custom theme if they saved one in the theme builder.
+pub async fn theme_options(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> TemplatePatch<ThemeOptionsTemplate> {
+    let themes = std::iter::once(state.themes.auto())
+        .chain(state.themes.all())
+        .cloned()
+        .chain(custom_seed(&state, &headers).map(|seed| (*state.custom_themes.get(seed)).clone()))
+        .collect();
+    TemplatePatch::new(ThemeOptionsTemplate { themes })
+}
+
+pub async fn switch_theme(
+    State(state): State<AppState>,
+    Query(params): Query<ThemeQuery>,
+    headers: HeaderMap,
+    viewport: ViewportContext,
+) -> Result<Response, AppError> {
+    let preference = if params.theme == CUSTOM && custom_seed(&state, &headers).is_some() {
+        CUSTOM
+    } else {
+        state
+            .themes
+            .get(&params.theme)
+            .map(|theme| theme.name.as_str())
+            .ok_or_else(|| AppError::bad_request(format!("Unknown theme: {}", params.theme)))?
+    };
+    let styles = ThemeStylesTemplate {
+        theme: page_theme_for(&state, preference, &headers, &viewport),
+    };
+    let signals = SignalPatch::new(&ThemeSignals { theme: preference })?;
+
+    Ok((
+        state.cookies.set(THEME_COOKIE, preference),
+        TemplatePatch::new(styles).signals(signals),
+    )
+        .into_response())
+}"#,
+    },
+    ExampleSource {
+        id: "live-counter",
+        language: "rust",
+        code: r#"use crate::{
+    error::AppError,
+    hypermedia::{LastEventId, Patches, SignalPatch, Subscription},
+    AppState,
+};
+use axum::extract::State;
+use datastar::prelude::PatchSignals;
+use serde::Serialize;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+const TOPIC: &str = "live-counter";
+
+#[derive(Serialize)]
+struct CounterSignals {
+    counter: Counter,
+}
+
+#[derive(Serialize)]
+struct Counter {
+    value: i64,
+}
+
+/// The count is only written after its event is published, so a panic while
+/// the lock was held cannot leave it half-updated and the poison is ignored.
+fn lock(counter: &Mutex<i64>) -> MutexGuard<'_, i64> {
+    counter.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn counter_patch(value: i64) -> Result<PatchSignals, AppError> {
+    Ok(SignalPatch::new(&CounterSignals {
+        counter: Counter { value },
+    })?
+    .into())
+}
+
+/// Streams the current value, then every increment from any client. A
+/// reconnecting tab first gets the increments it missed.
+pub async fn updates(
+    State(state): State<AppState>,
+    last_event_id: Option<LastEventId>,
+) -> Result<Subscription, AppError> {
+    let subscription = state.hub.subscribe(TOPIC, last_event_id);
+    let current = counter_patch(*lock(&state.counter))?;
+    Ok(subscription.with_initial(current))
+}
+
+pub async fn increment(State(state): State<AppState>) -> Result<Patches, AppError> {
+    let mut count = lock(&state.counter);
+    let value = *count + 1;
+    let receivers = state.hub.publish(TOPIC, counter_patch(value)?);
+    *count = value;
+    drop(count);
+    tracing::debug!(value, receivers, "Counter incremented");
+
+    // Everyone, including this client, hears about it on their stream
+    Ok(Patches::new())
+}"#,
+    },
+    ExampleSource {
+        id: "theme-builder",
+        language: "rust",
+        code: r##"use crate::{
+    contrast::{self, color},
+    error::AppError,
+    examples::theme_switcher::ThemeSignals,
+    handlers::page_accessibility,
+    hypermedia::{Patches, SignalPatch, Signals, TemplatePatch},
+    preferences::{CUSTOM_THEME_COOKIE, THEME_COOKIE},
+    syntax_highlight::highlight_code,
+    templates::{PageTheme, ThemeBuilderPreviewTemplate, ThemeStylesTemplate},
+    theme::{
+        generator::{Seed, CUSTOM},
+        ColorScheme,
+    },
+    viewport::{ViewportContext, ViewportSignal},
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+const SAMPLE: &str = r#"/// Greets everyone on the list
+fn greet(names: &[&str]) -> usize {
+    for name in names {
+        println!("Hello, {name}!");
+    }
+    names.len() * 2
+}"#;
+
+static HIGHLIGHTED_SAMPLE: LazyLock<String> =
+    LazyLock::new(|| highlight_code(SAMPLE, "rust").unwrap_or_else(|_| SAMPLE.to_string()));
+
+/// `$builder`, set by the sliders, the scheme radios and the color picker.
+#[derive(Deserialize)]
+pub struct BuilderSignals {
+    builder: Builder,
+    #[serde(default)]
+    viewport: Option<ViewportSignal>,
+}
+
+#[derive(Deserialize)]
+struct Builder {
+    hue: u16,
+    vibrancy: u8,
+    scheme: ColorScheme,
+    #[serde(default)]
+    brand: String,
+}
+
+impl Builder {
+    fn seed(&self) -> Seed {
+        Seed::new(self.hue, self.vibrancy, self.scheme)
+    }
+}
+
+/// The sliders moved to match a brand color.
+#[derive(Serialize)]
+struct SeedSignals {
+    builder: SeedValues,
+}
+
+#[derive(Serialize)]
+struct SeedValues {
+    hue: u16,
+    vibrancy: u8,
+}
+
+/// The generated theme, scoped to the preview, with its contrast summary.
+fn preview_patch(seed: Seed) -> TemplatePatch<ThemeBuilderPreviewTemplate> {
+    let theme = seed.generate();
+    let report = contrast::check_themes([&theme]);
+    let lowest_contrast = report
+        .checks
+        .iter()
+        .map(|check| check.ratio)
+        .fold(f64::INFINITY, f64::min);
+    TemplatePatch::new(ThemeBuilderPreviewTemplate {
+        declarations: theme.declarations().to_string(),
+        sample: HIGHLIGHTED_SAMPLE.clone(),
+        checks: report.checks.len(),
+        lowest_contrast,
+        passed: report.passed(),
+    })
+}
+
+pub async fn preview(
+    Signals(signals): Signals<BuilderSignals>,
+) -> TemplatePatch<ThemeBuilderPreviewTemplate> {
+    preview_patch(signals.builder.seed())
+}
+
+/// Seeds the sliders from `$builder.brand`, then previews the result.
+pub async fn from_brand(Signals(signals): Signals<BuilderSignals>) -> Result<Patches, AppError> {
+    let builder = signals.builder;
+    let brand = color::parse(&builder.brand)
+        .map_err(|e| AppError::bad_request(format!("Invalid brand color: {}", e)))?;
+    let seed = Seed::from_color(brand, builder.scheme);
+    let signals = SignalPatch::new(&SeedSignals {
+        builder: SeedValues {
+            hue: seed.hue,
+            vibrancy: seed.vibrancy,
+        },
+    })?;
+    Ok(Patches::new()
+        .elements(preview_patch(seed))
+        .signals(signals))
+}
+
+/// Stores the seed and switches the visitor to the custom theme. Only the
+/// seed is stored in the cookie; the theme generated from it is cached for
+/// the pages and stylesheet requests that follow.
+pub async fn save(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Signals(signals): Signals<BuilderSignals>,
+) -> Result<Response, AppError> {
+    let seed = signals.builder.seed();
+    let viewport = ViewportContext::from_headers(&headers).with_signal(signals.viewport);
+    let styles = ThemeStylesTemplate {
+        theme: PageTheme {
+            stylesheet: state
+                .custom_themes
+                .get(seed)
+                .stylesheet_url(viewport.density()),
+            preference: CUSTOM.to_string(),
+            accessibility: page_accessibility(&state, &headers),
+        },
+    };
+    let signals = SignalPatch::new(&ThemeSignals { theme: CUSTOM })?;
+
+    Ok((
+        state.cookies.set(CUSTOM_THEME_COOKIE, seed.encode()),
+        state.cookies.set(THEME_COOKIE, CUSTOM),
+        TemplatePatch::new(styles).signals(signals),
+    )
+        .into_response())
+}"##,
+    },
+    ExampleSource {
+        id: "accessibility-preferences",
+        language: "rust",
+        code: r#"use crate::{
+    accessibility::AccessibilityPreferences,
+    error::AppError,
+    handlers::page_accessibility_for,
+    hypermedia::{Signals, TemplatePatch},
+    preferences::ACCESSIBILITY_COOKIE,
+    templates::AccessibilityStylesTemplate,
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+/// `$accessibility`, seeded on every page from the cookie.
+#[derive(Deserialize)]
+pub struct AccessibilitySignals {
+    accessibility: AccessibilityPreferences,
+}
+
+/// Stores the preferences and swaps the page's accessibility styles.
+pub async fn update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Signals(signals): Signals<AccessibilitySignals>,
+) -> Result<Response, AppError> {
+    let preferences = signals
+        .accessibility
+        .validate()
+        .map_err(AppError::bad_request)?;
+    let styles = AccessibilityStylesTemplate {
+        accessibility: page_accessibility_for(preferences, &headers),
+    };
+
+    Ok((
+        state.cookies.set(ACCESSIBILITY_COOKIE, preferences.encode()),
+        TemplatePatch::new(styles),
+    )
+        .into_response())
+}"#,
+    },
+];
@@ -0,0 +1,67 @@
+// Auto-generated file - DO NOT EDIT
+use crate::{hypermedia::fragment_only, AppState};
+use axum::{routing, Router};
+
+#[path = "../src/examples/accessibility_preferences.rs"]
+pub mod accessibility_preferences;
+#[path = "../src/examples/active_search.rs"]
+pub mod active_search;
+#[path = "../src/examples/form_demo.rs"]
+pub mod form_demo;
+#[path = "../src/examples/hypermedia_demo.rs"]
+pub mod hypermedia_demo;
+#[path = "../src/examples/live_counter.rs"]
+pub mod live_counter;
+#[path = "../src/examples/theme_builder.rs"]
+pub mod theme_builder;
+#[path = "../src/examples/theme_switcher.rs"]
+pub mod theme_switcher;
+
+/// Mounts the endpoints declared with `@route` in each example header.
+pub fn register_examples(router: Router<AppState>) -> Router<AppState> {
+    router
+        .route(
+            "/examples/search",
+            routing::get(active_search::search).route_layer(fragment_only("/examples/active-search")),
+        )
+        .route(
+            "/examples/elements/get-items",
+            routing::get(hypermedia_demo::get_items).route_layer(fragment_only("/examples/hypermedia-demo")),
+        )
+        .route(
+            "/examples/elements/submit-form",
+            routing::post(form_demo::submit_form).route_layer(fragment_only("/examples/form-demo")),
+        )
+        .route(
+            "/examples/theme/switch",
+            routing::get(theme_switcher::switch_theme).route_layer(fragment_only("/examples/theme-switcher")),
+        )
+        .route(
+            "/examples/theme/options",
+            routing::get(theme_switcher::theme_options).route_layer(fragment_only("/examples/theme-switcher")),
+        )
+        .route(
+            "/examples/counter/updates",
+            routing::get(live_counter::updates).route_layer(fragment_only("/examples/live-counter")),
+        )
+        .route(
+            "/examples/counter/increment",
+            routing::post(live_counter::increment).route_layer(fragment_only("/examples/live-counter")),
+        )
+        .route(
+            "/examples/theme-builder/preview",
+            routing::get(theme_builder::preview).route_layer(fragment_only("/examples/theme-builder")),
+        )
+        .route(
+            "/examples/theme-builder/brand",
+            routing::get(theme_builder::from_brand).route_layer(fragment_only("/examples/theme-builder")),
+        )
+        .route(
+            "/examples/theme-builder/save",
+            routing::post(theme_builder::save).route_layer(fragment_only("/examples/theme-builder")),
+        )
+        .route(
+            "/examples/accessibility/update",
+            routing::post(accessibility_preferences::update).route_layer(fragment_only("/examples/accessibility-preferences")),
+        )
+}
@@ -0,0 +1,263 @@
+use crate::client_hints;
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Text sizes offered, in percent of the browser's default.
+pub const FONT_SCALES: &[u16] = &[90, 100, 110, 125, 150];
+
+/// Declarations that raise contrast on any theme: secondary text and the
+/// quieter syntax tokens are drawn in the primary text color, which every
+/// theme already checks against every surface.
+const HIGH_CONTRAST: &str = "--text-2: var(--text-1);
+--token-comment: var(--text-1);
+--token-punctuation: var(--text-1);";
+
+/// One on/off preference. `auto` follows the OS setting, from a client hint
+/// when the browser sent one and otherwise from a media query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Switch {
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+impl Switch {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Switch::Auto => "auto",
+            Switch::On => "on",
+            Switch::Off => "off",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(Switch::Auto),
+            "on" => Some(Switch::On),
+            "off" => Some(Switch::Off),
+            _ => None,
+        }
+    }
+
+    /// `auto` settled by a client hint, if there is one.
+    fn or_hint(self, hint: Option<bool>) -> Self {
+        match (self, hint) {
+            (Switch::Auto, Some(true)) => Switch::On,
+            (Switch::Auto, Some(false)) => Switch::Off,
+            (switch, _) => switch,
+        }
+    }
+}
+
+/// The visitor's accessibility preferences, as chosen and stored. They are
+/// `$accessibility` on every page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessibilityPreferences {
+    pub reduced_motion: Switch,
+    pub high_contrast: Switch,
+    /// One of [`FONT_SCALES`].
+    pub font_scale: u16,
+}
+
+impl Default for AccessibilityPreferences {
+    fn default() -> Self {
+        Self {
+            reduced_motion: Switch::Auto,
+            high_contrast: Switch::Auto,
+            font_scale: 100,
+        }
+    }
+}
+
+impl AccessibilityPreferences {
+    /// Rejects a font scale that is not one of [`FONT_SCALES`].
+    pub fn validate(self) -> Result<Self, String> {
+        if FONT_SCALES.contains(&self.font_scale) {
+            Ok(self)
+        } else {
+            Err(format!(
+                "Text size must be one of {:?}%, not {}%",
+                FONT_SCALES, self.font_scale
+            ))
+        }
+    }
+
+    /// `<reduced motion>-<high contrast>-<font scale>`, e.g. `auto-on-125`,
+    /// as stored in the cookie.
+    pub fn encode(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.reduced_motion.as_str(),
+            self.high_contrast.as_str(),
+            self.font_scale
+        )
+    }
+
+    /// Reads [`AccessibilityPreferences::encode`] back, rejecting anything
+    /// out of range.
+    pub fn decode(value: &str) -> Option<Self> {
+        let mut parts = value.split('-');
+        let preferences = Self {
+            reduced_motion: Switch::parse(parts.next()?)?,
+            high_contrast: Switch::parse(parts.next()?)?,
+            font_scale: parts.next()?.parse().ok()?,
+        };
+        parts.next().is_none().then_some(preferences).and_then(|p| p.validate().ok())
+    }
+
+    /// The preferences with `auto` settled by the `Sec-CH-Prefers-Reduced-Motion`
+    /// and `Sec-CH-Prefers-Contrast` hints. What stays `auto` is left to media
+    /// queries in [`AccessibilityPreferences::stylesheet`].
+    pub fn resolve(&self, headers: &HeaderMap) -> Self {
+        Self {
+            reduced_motion: self
+                .reduced_motion
+                .or_hint(client_hints::prefers_reduced_motion(headers)),
+            high_contrast: self
+                .high_contrast
+                .or_hint(client_hints::prefers_more_contrast(headers)),
+            font_scale: self.font_scale,
+        }
+    }
+
+    /// CSS variables for the preferences, applied on top of the theme:
+    /// `--font-scale` sizes the root font, `--motion-scale` multiplies
+    /// animation and transition durations, and high contrast overrides
+    /// theme colors.
+    pub fn stylesheet(&self) -> String {
+        let mut css = format!(
+            ":root {{\n--font-scale: {};",
+            f64::from(self.font_scale) / 100.0
+        );
+        match self.reduced_motion {
+            Switch::On => css.push_str("\n--motion-scale: 0;"),
+            Switch::Off => css.push_str("\n--motion-scale: 1;"),
+            Switch::Auto => {}
+        }
+        if self.high_contrast == Switch::On {
+            let _ = write!(css, "\n{HIGH_CONTRAST}");
+        }
+        css.push_str("\n}\n");
+
+        if self.reduced_motion == Switch::Auto {
+            css.push_str("@media (prefers-reduced-motion: reduce) {\n:root {\n--motion-scale: 0;\n}\n}\n");
+        }
+        if self.high_contrast == Switch::Auto {
+            let _ = write!(
+                css,
+                "@media (prefers-contrast: more) {{\n:root {{\n{HIGH_CONTRAST}\n}}\n}}\n"
+            );
+        }
+        css
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_hints::{PREFERS_CONTRAST, PREFERS_REDUCED_MOTION};
+
+    fn preferences(reduced_motion: Switch, high_contrast: Switch) -> AccessibilityPreferences {
+        AccessibilityPreferences {
+            reduced_motion,
+            high_contrast,
+            font_scale: 100,
+        }
+    }
+
+    fn hints(motion: Option<&str>, contrast: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(motion) = motion {
+            headers.insert(PREFERS_REDUCED_MOTION, motion.parse().unwrap());
+        }
+        if let Some(contrast) = contrast {
+            headers.insert(PREFERS_CONTRAST, contrast.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn hints_settle_only_auto() {
+        use Switch::*;
+        for (chosen, motion, contrast, resolved) in [
+            (Auto, Some("reduce"), Some("more"), On),
+            (Auto, Some("no-preference"), Some("no-preference"), Off),
+            (Auto, None, None, Auto),
+            (Auto, Some("bogus"), Some("bogus"), Auto),
+            (Auto, Some("\"reduce\""), Some("\"more\""), On),
+            (Auto, Some("no-preference"), Some("less"), Off),
+            (On, Some("no-preference"), Some("no-preference"), On),
+            (Off, Some("reduce"), Some("more"), Off),
+        ] {
+            let headers = hints(motion, contrast);
+            assert_eq!(
+                preferences(chosen, chosen).resolve(&headers),
+                preferences(resolved, resolved),
+                "{chosen:?} with {motion:?} and {contrast:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn media_queries_only_for_what_stays_auto() {
+        let auto = preferences(Switch::Auto, Switch::Auto).stylesheet();
+        assert!(auto.contains("@media (prefers-reduced-motion: reduce)"));
+        assert!(auto.contains("@media (prefers-contrast: more)"));
+        assert!(!auto.contains("--motion-scale: 1"));
+
+        let chosen = preferences(Switch::On, Switch::Off).stylesheet();
+        assert!(!chosen.contains("@media"));
+        assert!(chosen.contains("--motion-scale: 0;"));
+        assert!(!chosen.contains(HIGH_CONTRAST));
+
+        let settled = preferences(Switch::Auto, Switch::Auto)
+            .resolve(&hints(Some("no-preference"), Some("more")))
+            .stylesheet();
+        assert!(!settled.contains("@media"));
+        assert!(settled.contains("--motion-scale: 1;"));
+        assert!(settled.contains(HIGH_CONTRAST));
+    }
+
+    #[test]
+    fn decode_round_trips_and_rejects_anything_else() {
+        let stored = AccessibilityPreferences {
+            reduced_motion: Switch::On,
+            high_contrast: Switch::Auto,
+            font_scale: 125,
+        };
+        assert_eq!(stored.encode(), "on-auto-125");
+        assert_eq!(AccessibilityPreferences::decode("on-auto-125"), Some(stored));
+        for value in [
+            "",
+            "on-auto",
+            "on-auto-125-extra",
+            "yes-auto-125",
+            "on-auto-big",
+            "on-auto-120",
+        ] {
+            assert_eq!(AccessibilityPreferences::decode(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn only_offered_font_scales_are_valid() {
+        for &font_scale in FONT_SCALES {
+            let preferences = AccessibilityPreferences {
+                font_scale,
+                ..Default::default()
+            };
+            assert_eq!(preferences.validate(), Ok(preferences));
+        }
+        for font_scale in [0, 95, 200, 1000] {
+            let preferences = AccessibilityPreferences {
+                font_scale,
+                ..Default::default()
+            };
+            assert!(preferences.validate().is_err(), "{font_scale}");
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{
+    examples_gen::{get_example_source, get_examples},
+    syntax_highlight::highlight_code,
+    templates::ExampleWithHighlight,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Filters applied to the searchable part of the gallery.
+#[derive(Debug, Default)]
+pub struct ExampleQuery {
+    /// Example ids ranked by the full-text index, best first, or `None` when
+    /// there is no search text.
+    pub ranked_ids: Option<Vec<String>>,
+    pub category: String,
+    pub tag: String,
+}
+
+impl ExampleQuery {
+    fn rank(&self, example: &ExampleWithHighlight) -> Option<usize> {
+        match &self.ranked_ids {
+            Some(ids) => ids.iter().position(|id| *id == example.id),
+            None => Some(0),
+        }
+    }
+
+    fn matches_text(&self, example: &ExampleWithHighlight) -> bool {
+        self.rank(example).is_some()
+    }
+
+    fn matches_category(&self, example: &ExampleWithHighlight) -> bool {
+        self.category.is_empty() || example.category == self.category
+    }
+
+    fn matches_tag(&self, example: &ExampleWithHighlight) -> bool {
+        self.tag.is_empty() || example.tags.contains(&self.tag)
+    }
+}
+
+/// One facet chip: a category or tag value and how many examples it would show.
+#[derive(Debug, Clone)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExampleFacets {
+    pub categories: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
+}
+
+/// All examples with their frontend and backend code highlighted once at startup.
+///
+/// Handlers share the entries as `Arc`s, so rendering the gallery or a search
+/// result never re-parses the embedded YAML or re-runs the highlighter.
+pub struct ExampleCatalog {
+    examples: Vec<Arc<ExampleWithHighlight>>,
+}
+
+impl ExampleCatalog {
+    pub fn load() -> Self {
+        let examples = get_examples()
+            .into_iter()
+            .map(|ex| {
+                let highlighted_html =
+                    highlight_code(&ex.html, "html").unwrap_or_else(|_| ex.html.clone());
+                let highlighted_source = match get_example_source(&ex.id) {
+                    Some(source) => highlight_code(source.code, source.language)
+                        .unwrap_or_else(|_| source.code.to_string()),
+                    None => String::new(),
+                };
+                Arc::new(ExampleWithHighlight {
+                    id: ex.id,
+                    title: ex.title,
+                    description: ex.description,
+                    category: ex.category,
+                    tags: ex.tags,
+                    pinned: ex.pinned,
+                    layout: ex.layout,
+                    html: ex.html,
+                    highlighted_html,
+                    highlighted_source,
+                    backend_file: ex.backend_file,
+                })
+            })
+            .collect();
+
+        Self { examples }
+    }
+
+    /// Examples in display order (`@order`, then id).
+    pub fn all(&self) -> &[Arc<ExampleWithHighlight>] {
+        &self.examples
+    }
+
+    /// Pinned examples, shown in the featured section in `@order`.
+    pub fn featured(&self) -> impl Iterator<Item = &Arc<ExampleWithHighlight>> {
+        self.examples.iter().filter(|ex| ex.pinned)
+    }
+
+    /// Examples that live in the searchable part of the gallery.
+    pub fn unpinned(&self) -> impl Iterator<Item = &Arc<ExampleWithHighlight>> {
+        self.examples.iter().filter(|ex| !ex.pinned)
+    }
+
+    /// Unpinned examples matching every filter in the query, in rank order
+    /// when there is search text.
+    pub fn search(&self, query: &ExampleQuery) -> Vec<Arc<ExampleWithHighlight>> {
+        let mut results: Vec<_> = self
+            .unpinned()
+            .filter(|ex| query.matches_category(ex) && query.matches_tag(ex))
+            .filter_map(|ex| query.rank(ex).map(|rank| (rank, ex.clone())))
+            .collect();
+        results.sort_by_key(|(rank, _)| *rank);
+        results.into_iter().map(|(_, ex)| ex).collect()
+    }
+
+    /// Facet counts for the query. Each facet is counted with the other filters
+    /// applied but not its own, so picking a category still shows the
+    /// alternatives it could switch to.
+    pub fn facets(&self, query: &ExampleQuery) -> ExampleFacets {
+        let mut categories: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut tags: BTreeMap<&str, usize> = BTreeMap::new();
+
+        for example in self.unpinned() {
+            let text = query.matches_text(example);
+            let in_category = text && query.matches_tag(example);
+            *categories.entry(&example.category).or_default() += usize::from(in_category);
+
+            let in_tag = text && query.matches_category(example);
+            for tag in &example.tags {
+                *tags.entry(tag).or_default() += usize::from(in_tag);
+            }
+        }
+
+        let counts = |values: BTreeMap<&str, usize>, selected: &str| {
+            values
+                .into_iter()
+                .map(|(value, count)| FacetCount {
+                    value: value.to_string(),
+                    count,
+                    selected: value == selected,
+                })
+                .collect()
+        };
+
+        ExampleFacets {
+            categories: counts(categories, &query.category),
+            tags: counts(tags, &query.tag),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Arc<ExampleWithHighlight>> {
+        self.examples.iter().find(|ex| ex.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples_gen::ExampleLayout;
+
+    /// `(id, category, tags, pinned)` in display order.
+    const EXAMPLES: &[(&str, &str, &[&str], bool)] = &[
+        ("welcome", "basics", &["sse"], true),
+        ("counter", "basics", &["signals"], false),
+        ("search", "forms", &["sse", "signals"], false),
+        ("tour", "streaming", &["sse"], true),
+        ("upload", "forms", &["files"], false),
+        ("ticker", "streaming", &["sse"], false),
+    ];
+
+    fn catalog() -> ExampleCatalog {
+        let examples = EXAMPLES
+            .iter()
+            .map(|(id, category, tags, pinned)| {
+                Arc::new(ExampleWithHighlight {
+                    id: id.to_string(),
+                    title: id.to_string(),
+                    description: String::new(),
+                    category: category.to_string(),
+                    tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                    pinned: *pinned,
+                    layout: ExampleLayout::Card,
+                    html: String::new(),
+                    highlighted_html: String::new(),
+                    highlighted_source: String::new(),
+                    backend_file: String::new(),
+                })
+            })
+            .collect();
+        ExampleCatalog { examples }
+    }
+
+    fn ids<'a>(examples: impl IntoIterator<Item = &'a Arc<ExampleWithHighlight>>) -> Vec<&'a str> {
+        examples.into_iter().map(|ex| ex.id.as_str()).collect()
+    }
+
+    fn query(text: Option<&[&str]>, category: &str, tag: &str) -> ExampleQuery {
+        ExampleQuery {
+            ranked_ids: text.map(|ids| ids.iter().map(|id| id.to_string()).collect()),
+            category: category.to_string(),
+            tag: tag.to_string(),
+        }
+    }
+
+    fn counts(facets: &[FacetCount]) -> Vec<(&str, usize, bool)> {
+        facets
+            .iter()
+            .map(|facet| (facet.value.as_str(), facet.count, facet.selected))
+            .collect()
+    }
+
+    #[test]
+    fn pinned_examples_are_featured_in_order() {
+        let catalog = catalog();
+        assert_eq!(ids(catalog.featured()), ["welcome", "tour"]);
+        assert_eq!(
+            ids(catalog.unpinned()),
+            ["counter", "search", "upload", "ticker"]
+        );
+        assert_eq!(
+            ids(catalog.all()),
+            ["welcome", "counter", "search", "tour", "upload", "ticker"]
+        );
+    }
+
+    #[test]
+    fn search_leaves_out_pinned_examples() {
+        let catalog = catalog();
+        assert_eq!(
+            ids(&catalog.search(&ExampleQuery::default())),
+            ["counter", "search", "upload", "ticker"]
+        );
+        let ranked = query(Some(&["tour", "ticker", "welcome", "counter"]), "", "");
+        assert_eq!(ids(&catalog.search(&ranked)), ["ticker", "counter"]);
+    }
+
+    #[test]
+    fn search_combines_text_category_and_tag() {
+        let catalog = catalog();
+        let text = Some(&["upload", "search", "ticker"][..]);
+        assert_eq!(
+            ids(&catalog.search(&query(text, "forms", ""))),
+            ["upload", "search"]
+        );
+        assert_eq!(
+            ids(&catalog.search(&query(text, "forms", "sse"))),
+            ["search"]
+        );
+        assert!(catalog.search(&query(text, "basics", "")).is_empty());
+    }
+
+    #[test]
+    fn facets_are_counted_without_their_own_filter() {
+        let catalog = catalog();
+        let facets = catalog.facets(&query(None, "forms", "sse"));
+        // Categories under the `sse` tag, whichever category is picked
+        assert_eq!(
+            counts(&facets.categories),
+            [
+                ("basics", 0, false),
+                ("forms", 1, true),
+                ("streaming", 1, false)
+            ]
+        );
+        // Tags within `forms`, whichever tag is picked
+        assert_eq!(
+            counts(&facets.tags),
+            [("files", 1, false), ("signals", 1, false), ("sse", 1, true)]
+        );
+    }
+
+    #[test]
+    fn facets_are_counted_within_the_search() {
+        let catalog = catalog();
+        let text = Some(&["search", "ticker"][..]);
+        let facets = catalog.facets(&query(text, "", ""));
+        assert_eq!(
+            counts(&facets.categories),
+            [
+                ("basics", 0, false),
+                ("forms", 1, false),
+                ("streaming", 1, false)
+            ]
+        );
+        assert_eq!(
+            counts(&facets.tags),
+            [
+                ("files", 0, false),
+                ("signals", 1, false),
+                ("sse", 2, false)
+            ]
+        );
+
+        let facets = catalog.facets(&query(text, "streaming", ""));
+        assert_eq!(
+            counts(&facets.tags),
+            [
+                ("files", 0, false),
+                ("signals", 0, false),
+                ("sse", 1, false)
+            ]
+        );
+        assert_eq!(
+            counts(&facets.categories),
+            [
+                ("basics", 0, false),
+                ("forms", 1, false),
+                ("streaming", 1, true)
+            ]
+        );
+    }
+}
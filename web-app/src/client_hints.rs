@@ -0,0 +1,145 @@
+use crate::theme::ColorScheme;
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+/// The OS light/dark preference, `"light"` or `"dark"`.
+pub const PREFERS_COLOR_SCHEME: &str = "sec-ch-prefers-color-scheme";
+
+/// The OS reduced motion preference, `"reduce"` or `"no-preference"`.
+pub const PREFERS_REDUCED_MOTION: &str = "sec-ch-prefers-reduced-motion";
+
+/// The OS contrast preference, `"more"`, `"less"`, `"custom"` or
+/// `"no-preference"`.
+pub const PREFERS_CONTRAST: &str = "sec-ch-prefers-contrast";
+
+/// The layout viewport width in CSS pixels.
+pub const VIEWPORT_WIDTH: &str = "sec-ch-viewport-width";
+
+/// The device pixel ratio, device pixels per CSS pixel.
+pub const DPR: &str = "sec-ch-dpr";
+
+/// Hints browsers are asked to send on later requests.
+const ACCEPTED: &[&str] = &[
+    PREFERS_COLOR_SCHEME,
+    PREFERS_REDUCED_MOTION,
+    PREFERS_CONTRAST,
+    VIEWPORT_WIDTH,
+    DPR,
+];
+
+/// Hints that change the first paint. Chromium retries a navigation that
+/// lacked one of these once, with the hint, so even a first visit renders in
+/// the right scheme. Motion, contrast and density fall back to media queries,
+/// so they are not worth the retry.
+const CRITICAL: &[&str] = &[PREFERS_COLOR_SCHEME];
+
+/// Middleware advertising [`ACCEPTED`] and [`CRITICAL`] on HTML responses and
+/// marking them as varying on the hints.
+pub async fn advertise(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    if is_html {
+        let headers = response.headers_mut();
+        headers.insert("accept-ch", HeaderValue::from_str(&ACCEPTED.join(", ")).unwrap());
+        headers.insert("critical-ch", HeaderValue::from_str(&CRITICAL.join(", ")).unwrap());
+        for hint in ACCEPTED {
+            headers.append(header::VARY, HeaderValue::from_static(hint));
+        }
+    }
+    response
+}
+
+/// The OS color scheme from `Sec-CH-Prefers-Color-Scheme`, if the browser sent it.
+pub fn prefers_color_scheme(headers: &HeaderMap) -> Option<ColorScheme> {
+    match hint(headers, PREFERS_COLOR_SCHEME)? {
+        "light" => Some(ColorScheme::Light),
+        "dark" => Some(ColorScheme::Dark),
+        _ => None,
+    }
+}
+
+/// The request headers a page's theme is picked from: the cookies and the
+/// accepted hints.
+pub fn theme_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut theme_headers = HeaderMap::new();
+    for name in ACCEPTED.iter().copied().chain([header::COOKIE.as_str()]) {
+        for value in headers.get_all(name) {
+            theme_headers.append(header::HeaderName::from_static(name), value.clone());
+        }
+    }
+    theme_headers
+}
+
+/// Whether the OS asks for reduced motion, from `Sec-CH-Prefers-Reduced-Motion`.
+pub fn prefers_reduced_motion(headers: &HeaderMap) -> Option<bool> {
+    match hint(headers, PREFERS_REDUCED_MOTION)? {
+        "reduce" => Some(true),
+        "no-preference" => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether the OS asks for more contrast, from `Sec-CH-Prefers-Contrast`.
+/// `less` and `custom` read as no preference for more.
+pub fn prefers_more_contrast(headers: &HeaderMap) -> Option<bool> {
+    match hint(headers, PREFERS_CONTRAST)? {
+        "more" => Some(true),
+        "less" | "custom" | "no-preference" => Some(false),
+        _ => None,
+    }
+}
+
+/// A structured-header token hint, without its quotes.
+fn hint<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    Some(headers.get(name)?.to_str().ok()?.trim().trim_matches('"'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::send;
+    use axum::{body::Body, http::Request};
+
+    #[tokio::test]
+    async fn html_responses_ask_for_hints() {
+        let response = send(Request::get("/").body(Body::empty()).unwrap()).await;
+        let headers = response.headers();
+        assert_eq!(headers["accept-ch"], ACCEPTED.join(", "));
+        assert_eq!(headers["critical-ch"], PREFERS_COLOR_SCHEME);
+        let vary: Vec<_> = headers
+            .get_all(header::VARY)
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect();
+        for hint in ACCEPTED {
+            assert!(vary.contains(hint), "{hint} missing from {vary:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn other_responses_do_not() {
+        let response = send(Request::get("/health").body(Body::empty()).unwrap()).await;
+        assert!(response.headers().get("accept-ch").is_none());
+        assert!(response.headers().get("critical-ch").is_none());
+    }
+
+    #[test]
+    fn color_scheme_hint_is_read_without_quotes() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(prefers_color_scheme(&headers), None);
+        headers.insert(PREFERS_COLOR_SCHEME, HeaderValue::from_static("\"dark\""));
+        assert_eq!(prefers_color_scheme(&headers), Some(ColorScheme::Dark));
+        headers.insert(PREFERS_COLOR_SCHEME, HeaderValue::from_static("light"));
+        assert_eq!(prefers_color_scheme(&headers), Some(ColorScheme::Light));
+        headers.insert(PREFERS_COLOR_SCHEME, HeaderValue::from_static("sepia"));
+        assert_eq!(prefers_color_scheme(&headers), None);
+    }
+}
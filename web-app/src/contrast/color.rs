@@ -0,0 +1,212 @@
+/// An opaque sRGB color with channels in `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+impl Rgb {
+    pub fn from_hex(hex: u32) -> Self {
+        let channel = |shift: u32| f64::from((hex >> shift) & 0xff) / 255.0;
+        Self {
+            r: channel(16),
+            g: channel(8),
+            b: channel(0),
+        }
+    }
+
+    /// WCAG 2 relative luminance.
+    pub fn luminance(&self) -> f64 {
+        let linear = |c: f64| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// WCAG 2 contrast ratio, from 1 (none) to 21 (black on white).
+    pub fn contrast(&self, other: &Rgb) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+/// A color in OKLCH: perceptual lightness in `0.0..=1.0`, chroma from 0
+/// (gray) to about 0.37, and hue in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+}
+
+impl Oklch {
+    pub fn from_rgb(rgb: Rgb) -> Self {
+        let linear = |c: f64| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(rgb.r), linear(rgb.g), linear(rgb.b));
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+        let a = 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s;
+        let b = 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s;
+        Self {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            c: a.hypot(b),
+            h: b.atan2(a).to_degrees().rem_euclid(360.0),
+        }
+    }
+
+    /// Whether the color can be shown in sRGB without clipping.
+    pub fn in_gamut(self) -> bool {
+        self.linear_srgb()
+            .iter()
+            .all(|c| (-1e-4..=1.0 + 1e-4).contains(c))
+    }
+
+    /// The sRGB color, with channels outside the gamut clipped.
+    pub fn to_rgb(self) -> Rgb {
+        let encode = |c: f64| {
+            let c = c.clamp(0.0, 1.0);
+            if c <= 0.0031308 {
+                12.92 * c
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        };
+        let [r, g, b] = self.linear_srgb();
+        Rgb {
+            r: encode(r),
+            g: encode(g),
+            b: encode(b),
+        }
+    }
+
+    fn linear_srgb(self) -> [f64; 3] {
+        let (a, b) = (
+            self.c * self.h.to_radians().cos(),
+            self.c * self.h.to_radians().sin(),
+        );
+        let l = (self.l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m = (self.l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s = (self.l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+        [
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ]
+    }
+}
+
+/// Parses a literal CSS color: `#rgb`, `#rrggbb`, `rgb()`, `hsl()` or
+/// `oklch()`, in either the comma or the space separated syntax. Translucent
+/// colors are rejected, as their contrast depends on what is behind them.
+pub fn parse(value: &str) -> Result<Rgb, String> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        let digits = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 => hex.to_string(),
+            _ => return Err(format!("`{value}` is not a #rgb or #rrggbb color")),
+        };
+        return u32::from_str_radix(&digits, 16)
+            .map(Rgb::from_hex)
+            .map_err(|_| format!("`{value}` is not a hex color"));
+    }
+
+    let (function, arguments) = value
+        .strip_suffix(')')
+        .and_then(|value| value.split_once('('))
+        .ok_or_else(|| format!("`{value}` is not a color"))?;
+    let (channels, alpha) = match arguments.split_once('/') {
+        Some((channels, alpha)) => (channels, Some(alpha)),
+        None => (arguments, None),
+    };
+    let mut channels: Vec<&str> = channels
+        .split([',', ' '])
+        .filter(|part| !part.is_empty())
+        .collect();
+    let alpha = match (alpha, function) {
+        (Some(alpha), _) => Some(alpha.trim()),
+        (None, "rgba" | "hsla") if channels.len() == 4 => channels.pop(),
+        _ => None,
+    };
+    if let Some(alpha) = alpha {
+        if number(alpha, 1.0)? < 1.0 {
+            return Err(format!("`{value}` is translucent"));
+        }
+    }
+    let [first, second, third] = channels[..] else {
+        return Err(format!("`{value}` needs three channels"));
+    };
+
+    match function {
+        "rgb" | "rgba" => Ok(Rgb {
+            r: number(first, 255.0)?.clamp(0.0, 1.0),
+            g: number(second, 255.0)?.clamp(0.0, 1.0),
+            b: number(third, 255.0)?.clamp(0.0, 1.0),
+        }),
+        "hsl" | "hsla" => {
+            let hue = hue_degrees(first, value)?;
+            Ok(from_hsl(
+                hue,
+                number(second, 100.0)?.clamp(0.0, 1.0),
+                number(third, 100.0)?.clamp(0.0, 1.0),
+            ))
+        }
+        "oklch" => {
+            let hue = hue_degrees(third, value)?;
+            // A percentage chroma is relative to 0.4
+            let chroma = match second.strip_suffix('%') {
+                Some(_) => number(second, 1.0)? * 0.4,
+                None => number(second, 1.0)?,
+            };
+            Ok(Oklch {
+                l: number(first, 1.0)?.clamp(0.0, 1.0),
+                c: chroma.max(0.0),
+                h: hue,
+            }
+            .to_rgb())
+        }
+        _ => Err(format!("`{function}()` colors are not supported")),
+    }
+}
+
+/// A channel as a fraction: `50%` is 0.5, and a bare number is divided by
+/// `scale`.
+fn number(value: &str, scale: f64) -> Result<f64, String> {
+    let value = value.trim();
+    match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().map(|p| p / 100.0),
+        None => value.parse::<f64>().map(|n| n / scale),
+    }
+    .map_err(|_| format!("`{value}` is not a number"))
+}
+
+fn hue_degrees(hue: &str, value: &str) -> Result<f64, String> {
+    hue.trim_end_matches("deg")
+        .parse::<f64>()
+        .map_err(|_| format!("`{value}` has a hue that is not a number of degrees"))
+}
+
+fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Rgb {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let channel = |n: f64| {
+        let k = (n + hue.rem_euclid(360.0) / 30.0) % 12.0;
+        lightness - chroma / 2.0 * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    Rgb {
+        r: channel(0.0),
+        g: channel(8.0),
+        b: channel(4.0),
+    }
+}
@@ -0,0 +1,313 @@
+pub mod color;
+pub mod palette;
+
+use crate::{
+    settings::Settings,
+    theme::{Theme, ThemeRegistry},
+};
+use color::Rgb;
+use std::fmt;
+
+/// WCAG 2 level AA minimum for normal-size text.
+pub const AA_NORMAL_TEXT: f64 = 4.5;
+
+const TEXT: &[&str] = &["--text-1", "--text-2"];
+const SURFACES: &[&str] = &[
+    "--surface-0",
+    "--surface-1",
+    "--surface-2",
+    "--surface-3",
+    "--surface-4",
+];
+/// Code blocks, where the syntax tokens are drawn (see `static/styles.css`).
+const CODE_SURFACE: &str = "--surface-2";
+
+/// One foreground/background pair of a theme. Gradients are measured at
+/// every color stop and the pair is reported at its worst.
+#[derive(Debug, Clone)]
+pub struct ContrastCheck {
+    pub theme: String,
+    pub foreground: String,
+    pub background: String,
+    pub ratio: f64,
+}
+
+impl ContrastCheck {
+    pub fn passes(&self) -> bool {
+        self.ratio >= AA_NORMAL_TEXT
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ContrastReport {
+    pub checks: Vec<ContrastCheck>,
+    /// Values that could not be resolved to a color, so were not checked.
+    pub problems: Vec<String>,
+    /// Pairs drawn on an Open Props gradient preset, which has no embedded
+    /// stops to measure.
+    pub skipped: Vec<String>,
+}
+
+impl ContrastReport {
+    pub fn failures(&self) -> impl Iterator<Item = &ContrastCheck> {
+        self.checks.iter().filter(|check| !check.passes())
+    }
+
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty() && self.failures().next().is_none()
+    }
+}
+
+impl fmt::Display for ContrastReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(
+                f,
+                "{:<6} {:<8} {:<18} on {:<12} {:>5.2}",
+                if check.passes() { "ok" } else { "FAIL" },
+                check.theme,
+                check.foreground,
+                check.background,
+                check.ratio
+            )?;
+        }
+        for skipped in &self.skipped {
+            writeln!(f, "skip   {}", skipped)?;
+        }
+        for problem in &self.problems {
+            writeln!(f, "ERROR  {}", problem)?;
+        }
+        write!(
+            f,
+            "{} pairs checked, {} below WCAG AA ({}:1), {} skipped, {} unresolved",
+            self.checks.len(),
+            self.failures().count(),
+            AA_NORMAL_TEXT,
+            self.skipped.len(),
+            self.problems.len()
+        )
+    }
+}
+
+/// Checks text on every surface and syntax tokens on the code surface, for
+/// every theme in the registry.
+pub fn check(themes: &ThemeRegistry) -> ContrastReport {
+    check_themes(themes.all())
+}
+
+/// [`check`] for any themes, e.g. generated ones.
+pub fn check_themes<'a>(themes: impl IntoIterator<Item = &'a Theme>) -> ContrastReport {
+    let mut report = ContrastReport::default();
+    for theme in themes {
+        let variables = theme.variables();
+        let tokens = variables
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| name.starts_with("--token-"));
+        let pairs = TEXT
+            .iter()
+            .flat_map(|text| SURFACES.iter().map(move |surface| (*text, *surface)))
+            .chain(tokens.map(|token| (token, CODE_SURFACE)));
+
+        for (foreground, background) in pairs {
+            let colors = resolve_variable(variables, foreground, 0).and_then(|foreground| {
+                Ok((foreground, resolve_variable(variables, background, 0)?))
+            });
+            match colors {
+                Ok((foregrounds, backgrounds))
+                    if foregrounds.is_empty() || backgrounds.is_empty() =>
+                {
+                    report.skipped.push(format!(
+                        "{}: {} on {}: Open Props gradient",
+                        theme.name, foreground, background
+                    ))
+                }
+                Ok((foregrounds, backgrounds)) => {
+                    let ratio = foregrounds
+                        .iter()
+                        .flat_map(|fg| backgrounds.iter().map(move |bg| fg.contrast(bg)))
+                        .fold(f64::INFINITY, f64::min);
+                    report.checks.push(ContrastCheck {
+                        theme: theme.name.clone(),
+                        foreground: foreground.to_string(),
+                        background: background.to_string(),
+                        ratio,
+                    });
+                }
+                Err(problem) => report.problems.push(format!(
+                    "{}: {} on {}: {}",
+                    theme.name, foreground, background, problem
+                )),
+            }
+        }
+    }
+    report.problems.dedup();
+    report
+}
+
+/// The `check-contrast` subcommand: prints the report for the configured
+/// themes and fails if any pair is below AA.
+pub fn run(settings: &Settings) -> anyhow::Result<()> {
+    let themes = ThemeRegistry::load(&Settings::config_dir().join("themes"), &settings.themes)?;
+    let report = check(&themes);
+    println!("{}", report);
+    if !report.passed() {
+        anyhow::bail!("Themes do not meet WCAG AA");
+    }
+    Ok(())
+}
+
+fn resolve_variable(
+    variables: &[(String, String)],
+    name: &str,
+    depth: usize,
+) -> Result<Vec<Rgb>, String> {
+    let (_, value) = variables
+        .iter()
+        .find(|(candidate, _)| candidate == name)
+        .ok_or_else(|| format!("`{name}` is not set"))?;
+    resolve(variables, value, depth + 1)
+}
+
+/// The colors `value` can paint: one for a color, every stop for a gradient,
+/// none for an Open Props gradient preset.
+fn resolve(variables: &[(String, String)], value: &str, depth: usize) -> Result<Vec<Rgb>, String> {
+    if depth > 8 {
+        return Err(format!("`{value}` nests too many var() references"));
+    }
+    let value = value.trim();
+
+    if let Some(inner) = value.strip_prefix("var(").and_then(|v| v.strip_suffix(')')) {
+        let parts = split_top_level(inner, ',');
+        let (name, fallback) = match parts.as_slice() {
+            [name] => (name.trim(), None),
+            [name, fallback @ ..] => (name.trim(), Some(fallback.join(","))),
+            [] => return Err("`var()` without a name".to_string()),
+        };
+        if variables.iter().any(|(candidate, _)| candidate == name) {
+            return resolve_variable(variables, name, depth);
+        }
+        if let Some(hex) = name.strip_prefix("--").and_then(palette::lookup) {
+            return Ok(vec![Rgb::from_hex(hex)]);
+        }
+        if name.strip_prefix("--").is_some_and(palette::is_gradient) {
+            return Ok(Vec::new());
+        }
+        return match fallback {
+            Some(fallback) => resolve(variables, &fallback, depth + 1),
+            None => Err(format!(
+                "`{name}` is not an Open Props color or theme variable"
+            )),
+        };
+    }
+
+    if let Some((function, arguments)) = value
+        .strip_suffix(')')
+        .and_then(|value| value.split_once('('))
+        .filter(|(function, _)| function.ends_with("gradient"))
+    {
+        // Stops are `<color> [<position>]`; parts without a color, like
+        // `135deg` or `from 90deg at 50% 0%`, are skipped
+        let mut stops: Vec<Rgb> = Vec::new();
+        for part in split_top_level(arguments, ',') {
+            let Some(color) = split_top_level(&part, ' ').into_iter().next() else {
+                continue;
+            };
+            let color = color.trim();
+            if color.starts_with('#') || color.contains('(') {
+                stops.extend(resolve(variables, color, depth + 1)?);
+            }
+        }
+        if stops.is_empty() {
+            return Err(format!(
+                "`{function}()` has no color stops that can be checked"
+            ));
+        }
+        return Ok(stops);
+    }
+
+    color::parse(value).map(|color| vec![color])
+}
+
+/// Splits on `separator` outside parentheses.
+fn split_top_level(value: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut depth = 0usize;
+    for c in value.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(c);
+    }
+    parts.retain(|part| !part.trim().is_empty());
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn black_on_white_is_21_to_1() {
+        let black = color::parse("#000").unwrap();
+        let white = color::parse("hsl(0 0% 100%)").unwrap();
+        assert!((black.contrast(&white) - 21.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn oklch_matches_srgb() {
+        let red = color::parse("oklch(62.8% 0.2577 29.23)").unwrap();
+        assert!((red.r - 1.0).abs() < 1e-3 && red.g.abs() < 1e-3 && red.b.abs() < 1e-3);
+        let back = color::Oklch::from_rgb(red);
+        assert!((back.l - 0.628).abs() < 1e-3 && (back.h - 29.23).abs() < 0.1);
+    }
+
+    #[test]
+    fn gradients_resolve_to_their_stops() {
+        let variables = vec![("--stop".to_string(), "var(--gray-5)".to_string())];
+        let stops = resolve(
+            &variables,
+            "linear-gradient(135deg, var(--gray-2), var(--stop) 50%, #000)",
+            0,
+        )
+        .unwrap();
+        assert_eq!(stops.len(), 3);
+        assert_eq!(stops[1], Rgb::from_hex(0xadb5bd));
+        assert_eq!(resolve(&variables, "var(--gradient-6)", 0), Ok(Vec::new()));
+        assert!(resolve(&variables, "var(--gradient-31)", 0).is_err());
+    }
+
+    #[test]
+    fn every_theme_surface_is_checked_or_skipped() {
+        let settings = Settings::init().expect("settings load");
+        let themes = ThemeRegistry::load(&Settings::config_dir().join("themes"), &settings.themes)
+            .expect("themes load");
+        let report = check(&themes);
+        for theme in themes
+            .all()
+            .iter()
+            .filter(|theme| !theme.variables().is_empty())
+        {
+            let prefix = format!("{}: --text-", theme.name);
+            let text_pairs = report
+                .checks
+                .iter()
+                .filter(|check| {
+                    check.theme == theme.name && TEXT.contains(&check.foreground.as_str())
+                })
+                .count()
+                + report
+                    .skipped
+                    .iter()
+                    .filter(|skipped| skipped.starts_with(&prefix))
+                    .count();
+            assert_eq!(text_pairs, TEXT.len() * SURFACES.len(), "\n{}", report);
+        }
+    }
+}
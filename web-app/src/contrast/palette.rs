@@ -0,0 +1,157 @@
+/// `--<hue>-0` to `--<hue>-12` for every Open Props 1.x hue, as `0xRRGGBB`.
+/// An embedded copy, so themes written against `var(--<hue>-<shade>)` can be
+/// checked without a browser.
+const COLORS: &[(&str, [u32; 13])] = &[
+    (
+        "gray",
+        [
+            0xf8f9fa, 0xf1f3f5, 0xe9ecef, 0xdee2e6, 0xced4da, 0xadb5bd, 0x868e96, 0x495057,
+            0x343a40, 0x212529, 0x16191d, 0x0d0f12, 0x030507,
+        ],
+    ),
+    (
+        "stone",
+        [
+            0xf8fafb, 0xf2f4f6, 0xebedef, 0xe0e4e5, 0xd1d6d8, 0xb1b6b9, 0x979b9d, 0x7e8282,
+            0x666968, 0x50514f, 0x3a3a37, 0x252521, 0x121210,
+        ],
+    ),
+    (
+        "red",
+        [
+            0xfff5f5, 0xffe3e3, 0xffc9c9, 0xffa8a8, 0xff8787, 0xff6b6b, 0xfa5252, 0xf03e3e,
+            0xe03131, 0xc92a2a, 0xb02525, 0x962020, 0x7d1a1a,
+        ],
+    ),
+    (
+        "pink",
+        [
+            0xfff0f6, 0xffdeeb, 0xfcc2d7, 0xfaa2c1, 0xf783ac, 0xf06595, 0xe64980, 0xd6336c,
+            0xc2255c, 0xa61e4d, 0x8c1941, 0x731536, 0x59102a,
+        ],
+    ),
+    (
+        "purple",
+        [
+            0xf8f0fc, 0xf3d9fa, 0xeebefa, 0xe599f7, 0xda77f2, 0xcc5de8, 0xbe4bdb, 0xae3ec9,
+            0x9c36b5, 0x862e9c, 0x702682, 0x5a1e69, 0x44174f,
+        ],
+    ),
+    (
+        "violet",
+        [
+            0xf3f0ff, 0xe5dbff, 0xd0bfff, 0xb197fc, 0x9775fa, 0x845ef7, 0x7950f2, 0x7048e8,
+            0x6741d9, 0x5f3dc4, 0x5235ab, 0x462d91, 0x3a2578,
+        ],
+    ),
+    (
+        "indigo",
+        [
+            0xedf2ff, 0xdbe4ff, 0xbac8ff, 0x91a7ff, 0x748ffc, 0x5c7cfa, 0x4c6ef5, 0x4263eb,
+            0x3b5bdb, 0x364fc7, 0x2f44ad, 0x283a94, 0x21307a,
+        ],
+    ),
+    (
+        "blue",
+        [
+            0xe7f5ff, 0xd0ebff, 0xa5d8ff, 0x74c0fc, 0x4dabf7, 0x339af0, 0x228be6, 0x1c7ed6,
+            0x1971c2, 0x1864ab, 0x145591, 0x114678, 0x0d375e,
+        ],
+    ),
+    (
+        "cyan",
+        [
+            0xe3fafc, 0xc5f6fa, 0x99e9f2, 0x66d9e8, 0x3bc9db, 0x22b8cf, 0x15aabf, 0x1098ad,
+            0x0c8599, 0x0b7285, 0x095c6b, 0x074652, 0x053038,
+        ],
+    ),
+    (
+        "teal",
+        [
+            0xe6fcf5, 0xc3fae8, 0x96f2d7, 0x63e6be, 0x38d9a9, 0x20c997, 0x12b886, 0x0ca678,
+            0x099268, 0x087f5b, 0x066649, 0x054d37, 0x033325,
+        ],
+    ),
+    (
+        "green",
+        [
+            0xebfbee, 0xd3f9d8, 0xb2f2bb, 0x8ce99a, 0x69db7c, 0x51cf66, 0x40c057, 0x37b24d,
+            0x2f9e44, 0x2b8a3e, 0x237032, 0x1b5727, 0x133d1b,
+        ],
+    ),
+    (
+        "lime",
+        [
+            0xf4fce3, 0xe9fac8, 0xd8f5a2, 0xc0eb75, 0xa9e34b, 0x94d82d, 0x82c91e, 0x74b816,
+            0x66a80f, 0x5c940d, 0x4c7a0b, 0x3c6109, 0x2c4706,
+        ],
+    ),
+    (
+        "yellow",
+        [
+            0xfff9db, 0xfff3bf, 0xffec99, 0xffe066, 0xffd43b, 0xfcc419, 0xfab005, 0xf59f00,
+            0xf08c00, 0xe67700, 0xb35c00, 0x804200, 0x663500,
+        ],
+    ),
+    (
+        "orange",
+        [
+            0xfff4e6, 0xffe8cc, 0xffd8a8, 0xffc078, 0xffa94d, 0xff922b, 0xfd7e14, 0xf76707,
+            0xe8590c, 0xd9480f, 0xbf400d, 0x99330b, 0x802b09,
+        ],
+    ),
+    (
+        "choco",
+        [
+            0xfff8dc, 0xfce1bc, 0xf7ca9e, 0xf1b280, 0xe99b62, 0xdf8545, 0xd46e25, 0xbd5f1b,
+            0xa45117, 0x8a4513, 0x703a13, 0x572f12, 0x3d210d,
+        ],
+    ),
+    (
+        "brown",
+        [
+            0xfaf4eb, 0xede0d1, 0xe0cab7, 0xd3b79e, 0xc5a285, 0xb78f6d, 0xa87c56, 0x956b47,
+            0x825b3a, 0x6f4b2d, 0x5e3a21, 0x4e2b15, 0x422412,
+        ],
+    ),
+    (
+        "sand",
+        [
+            0xf8fafb, 0xe6e4dc, 0xd5cfbd, 0xc2b9a0, 0xaea283, 0x9a8a68, 0x86744f, 0x705f3f,
+            0x5c4d33, 0x4c3f2d, 0x3d3226, 0x2f271f, 0x211c17,
+        ],
+    ),
+    (
+        "camo",
+        [
+            0xf9fbe7, 0xe8ed9c, 0xd2df4e, 0xc2ce34, 0xb5bb2e, 0xa7a827, 0x999621, 0x8c851c,
+            0x7e7416, 0x6d6414, 0x5d5411, 0x4d460e, 0x36300a,
+        ],
+    ),
+    (
+        "jungle",
+        [
+            0xecfeb0, 0xdef39a, 0xd0e884, 0xc2dd6e, 0xb5d15b, 0xa8c648, 0x9bbb36, 0x8fb024,
+            0x84a513, 0x7a9908, 0x658006, 0x516605, 0x3d4d04,
+        ],
+    ),
+];
+
+/// The color of Open Props variable `name` (without `--`), e.g. `gray-12`.
+pub fn lookup(name: &str) -> Option<u32> {
+    let (hue, shade) = name.rsplit_once('-')?;
+    let shade: usize = shade.parse().ok()?;
+    COLORS
+        .iter()
+        .find(|(candidate, _)| *candidate == hue)
+        .and_then(|(_, shades)| shades.get(shade).copied())
+}
+
+/// Whether `name` (without `--`) is one of the Open Props `--gradient-1` to
+/// `--gradient-30` presets. Their stops are not embedded, so surfaces using
+/// them are skipped rather than checked.
+pub fn is_gradient(name: &str) -> bool {
+    name.strip_prefix("gradient-")
+        .and_then(|number| number.parse::<u32>().ok())
+        .is_some_and(|number| (1..=30).contains(&number))
+}
@@ -0,0 +1,323 @@
+use crate::syntax_highlight::highlight_code;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+include!("../generated/docs_data.rs");
+
+/// A page of the bundled Datastar reference, split out of the crawled markdown.
+///
+/// Crawled pages look like `# Title`, `Source: <url>`, `---`, then the body.
+/// Section `index.md` pages are generated link lists without that preamble.
+#[derive(Debug, Clone)]
+pub struct DocPage {
+    pub section: &'static str,
+    pub slug: &'static str,
+    pub title: String,
+    pub source_url: Option<String>,
+    /// Markdown after the crawler preamble.
+    pub body: &'static str,
+    /// `body` rendered to HTML with highlighted code blocks.
+    pub html: String,
+}
+
+impl DocPage {
+    fn parse(doc: &EmbeddedDoc) -> Self {
+        let markdown = doc.markdown;
+        let mut lines = markdown.lines().filter(|line| !line.trim().is_empty());
+        let heading = lines.next().and_then(|line| line.strip_prefix("# "));
+        let source_url = lines.next().and_then(|line| line.strip_prefix("Source: "));
+        let has_preamble = heading.is_some() && source_url.is_some() && lines.next() == Some("---");
+
+        let (body, source_url) = if has_preamble {
+            let body = markdown
+                .split_once("\n---\n")
+                .map_or(markdown, |(_, body)| body.trim_start());
+            (body, source_url.map(|url| url.trim().to_string()))
+        } else {
+            let body = markdown
+                .split_once('\n')
+                .map_or("", |(_, body)| body.trim_start());
+            (body, None)
+        };
+
+        let title = if doc.slug == "index" {
+            "Overview".to_string()
+        } else {
+            heading
+                .map(|title| title.trim().to_string())
+                .unwrap_or_else(|| doc.slug.replace('_', " "))
+        };
+
+        Self {
+            section: doc.section,
+            slug: doc.slug,
+            title,
+            source_url,
+            body,
+            html: render_markdown(body, doc.section),
+        }
+    }
+
+    /// Section landing pages (`index.md`) only list links to the other pages.
+    pub fn is_index(&self) -> bool {
+        self.slug == "index"
+    }
+
+    pub fn url(&self) -> String {
+        format!("/docs/{}/{}", self.section, self.slug)
+    }
+}
+
+/// A sidebar group: one section of `index.json` and its pages.
+#[derive(Debug, Clone)]
+pub struct DocSection {
+    pub name: &'static str,
+    pub title: String,
+    pub pages: Vec<DocLink>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocLink {
+    pub title: String,
+    pub url: String,
+}
+
+/// The Datastar docs embedded at build time from `ref/datastar-docs`.
+pub struct DocsLibrary {
+    pages: Vec<DocPage>,
+    sections: Vec<DocSection>,
+}
+
+impl DocsLibrary {
+    pub fn load() -> Self {
+        let pages: Vec<DocPage> = EMBEDDED_DOCS.iter().map(DocPage::parse).collect();
+
+        // Sections keep their `index.json` order; pages are listed with the
+        // overview first, then alphabetically.
+        let mut sections: Vec<DocSection> = Vec::new();
+        for page in &pages {
+            if !sections.iter().any(|section| section.name == page.section) {
+                let mut section_pages: Vec<&DocPage> =
+                    pages.iter().filter(|p| p.section == page.section).collect();
+                section_pages.sort_by(|a, b| {
+                    b.is_index()
+                        .cmp(&a.is_index())
+                        .then_with(|| a.title.cmp(&b.title))
+                });
+                sections.push(DocSection {
+                    name: page.section,
+                    title: section_title(page.section),
+                    pages: section_pages
+                        .into_iter()
+                        .map(|p| DocLink {
+                            title: p.title.clone(),
+                            url: p.url(),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        Self { pages, sections }
+    }
+
+    pub fn pages(&self) -> &[DocPage] {
+        &self.pages
+    }
+
+    pub fn sections(&self) -> &[DocSection] {
+        &self.sections
+    }
+
+    pub fn get(&self, section: &str, slug: &str) -> Option<&DocPage> {
+        self.pages
+            .iter()
+            .find(|page| page.section == section && page.slug == slug)
+    }
+
+    /// Where `/docs` sends readers: the first page of the first section.
+    pub fn landing_url(&self) -> Option<&str> {
+        self.sections
+            .first()
+            .and_then(|section| section.pages.first())
+            .map(|page| page.url.as_str())
+    }
+}
+
+/// `how_tos` -> `How tos`
+fn section_title(section: &str) -> String {
+    let title = section.replace('_', " ");
+    let mut chars = title.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => title,
+    }
+}
+
+/// Renders crawled markdown to HTML.
+///
+/// Code fences go through the syntax highlighter, links to data-star.dev pages
+/// we bundle are pointed at `/docs`, and raw HTML in the markdown is escaped
+/// so Datastar attributes in the docs never become live on our pages.
+fn render_markdown(markdown: &str, section: &str) -> String {
+    let markdown = heading_anchors_to_ids(markdown);
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_HEADING_ATTRIBUTES;
+
+    let mut events = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+
+    for event in Parser::new_ext(&markdown, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .chars()
+                        .filter(|c| c.is_ascii_alphanumeric())
+                        .collect(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code_block = Some((language, String::new()));
+            }
+            Event::Text(text) if code_block.is_some() => {
+                if let Some((_, code)) = code_block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let (language, code) = code_block.take().unwrap_or_default();
+                let code = strip_line_numbers(&code);
+                let highlighted = highlight_code(&code, &language)
+                    .unwrap_or_else(|_| html_escape(&code));
+                let language = if language.is_empty() { "text" } else { &language };
+                events.push(Event::Html(CowStr::from(format!(
+                    "<pre><code class=\"language-{language}\">{highlighted}</code></pre>\n"
+                ))));
+            }
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => events.push(Event::Start(Tag::Link {
+                link_type,
+                dest_url: CowStr::from(rewrite_link(&dest_url, section)),
+                title,
+                id,
+            })),
+            Event::Html(raw) | Event::InlineHtml(raw) => events.push(Event::Text(raw)),
+            other => events.push(other),
+        }
+    }
+
+    let mut out = String::with_capacity(markdown.len() * 2);
+    html::push_html(&mut out, events.into_iter());
+    out
+}
+
+/// The crawler leaves headings as `## Title [#](#anchor)`; turn the anchor into
+/// a heading id (`## Title {#anchor}`) so in-page links keep working.
+fn heading_anchors_to_ids(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(|line| {
+            if !line.starts_with('#') {
+                return line.to_string();
+            }
+            match line.rsplit_once(" [#](#") {
+                Some((heading, anchor)) if anchor.ends_with(')') => {
+                    format!("{heading} {{#{}}}", anchor.trim_end_matches(')'))
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Crawled code blocks carry the site's line numbers in a right-aligned
+/// gutter as wide as the last number (` 9<div>`, `10</div>`); drop them when
+/// every line has the next one.
+pub fn strip_line_numbers(code: &str) -> String {
+    let lines: Vec<&str> = code.lines().collect();
+    let width = lines.len().to_string().len();
+    let stripped: Option<Vec<&str>> = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let gutter = format!("{:>width$}", index + 1);
+            line.strip_prefix(gutter.as_str())
+        })
+        .collect();
+    match stripped {
+        Some(lines) if !lines.is_empty() => lines.join("\n"),
+        _ => code.to_string(),
+    }
+}
+
+/// Points site-relative links at the bundled copy: `/reference/actions#get`
+/// becomes `/docs/reference/actions#get`, and index links like
+/// `backend_requests.md` resolve within the current section.
+fn rewrite_link(url: &str, section: &str) -> String {
+    let (path, fragment) = match url.split_once('#') {
+        Some((path, fragment)) => (path, format!("#{fragment}")),
+        None => (url, String::new()),
+    };
+
+    if let Some(slug) = path.strip_suffix(".md").filter(|p| !p.contains('/')) {
+        return format!("/docs/{section}/{slug}{fragment}");
+    }
+    if let Some((doc_section, slug)) = path.trim_start_matches('/').split_once('/') {
+        let known = EMBEDDED_DOCS
+            .iter()
+            .any(|doc| doc.section == doc_section && doc.slug == slug);
+        if path.starts_with('/') && known {
+            return format!("/docs/{doc_section}/{slug}{fragment}");
+        }
+    }
+    if path.starts_with('/') {
+        // Other site pages we don't bundle
+        return format!("https://data-star.dev{url}");
+    }
+    url.to_string()
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_sequential_line_numbers() {
+        let numbered = (1..=10)
+            .map(|n| format!("{n:>2}line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let plain = (1..=10)
+            .map(|n| format!("line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(strip_line_numbers(&numbered), plain);
+        assert_eq!(strip_line_numbers("1event: patch\n2data: x"), "event: patch\ndata: x");
+    }
+
+    #[test]
+    fn keeps_code_that_starts_with_digits() {
+        for code in [
+            "200 OK\n404 Not Found",
+            "1\n1\n2\n3",
+            "1. first\n3. third",
+            "2d6 damage",
+        ] {
+            assert_eq!(strip_line_numbers(code), code);
+        }
+    }
+}
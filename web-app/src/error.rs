@@ -1,18 +1,41 @@
+use crate::{
+    client_hints,
+    handlers::page_theme,
+    hypermedia::{request::DatastarRequest, TemplatePatch},
+    request_id::RequestId,
+    templates::{ErrorPageTemplate, ErrorToastTemplate},
+    AppState,
+};
 use axum::{
-    http::StatusCode,
+    extract::{
+        rejection::{FormRejection, PathRejection, QueryRejection},
+        Request, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
+use datastar::consts::ElementPatchMode;
 use serde_json::json;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
     #[error("Bad request: {0}")]
     BadRequest(String),
-    
+
     #[error("Not found: {0}")]
     NotFound(String),
-    
+
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+
+    #[error("Not acceptable: {0}")]
+    NotAcceptable(String),
+
+    #[error("Unprocessable entity: {0}")]
+    UnprocessableEntity(String),
+
     #[error("Internal server error")]
     InternalServerError(#[from] anyhow::Error),
 }
@@ -21,28 +44,290 @@ impl AppError {
     pub fn bad_request(message: impl Into<String>) -> Self {
         AppError::BadRequest(message.into())
     }
-    
-    #[allow(dead_code)]
+
     pub fn not_found(message: impl Into<String>) -> Self {
         AppError::NotFound(message.into())
     }
+
+    pub fn method_not_allowed(message: impl Into<String>) -> Self {
+        AppError::MethodNotAllowed(message.into())
+    }
+
+    pub fn not_acceptable(message: impl Into<String>) -> Self {
+        AppError::NotAcceptable(message.into())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            AppError::NotAcceptable(_) => StatusCode::NOT_ACCEPTABLE,
+            AppError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable machine-readable code; clients may match on it, so never rename one.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::NotFound(_) => "not_found",
+            AppError::MethodNotAllowed(_) => "method_not_allowed",
+            AppError::NotAcceptable(_) => "not_acceptable",
+            AppError::UnprocessableEntity(_) => "unprocessable_entity",
+            AppError::InternalServerError(_) => "internal_error",
+        }
+    }
+}
+
+/// An extractor rejection as the matching [`AppError`]: the body axum would
+/// have sent becomes the message, and the status is kept where an error
+/// variant has it.
+fn from_rejection(status: StatusCode, message: String) -> AppError {
+    match status {
+        StatusCode::NOT_FOUND => AppError::NotFound(message),
+        StatusCode::UNPROCESSABLE_ENTITY => AppError::UnprocessableEntity(message),
+        status if status.is_server_error() => AppError::InternalServerError(anyhow::anyhow!(message)),
+        _ => AppError::BadRequest(message),
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        from_rejection(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<FormRejection> for AppError {
+    fn from(rejection: FormRejection) -> Self {
+        from_rejection(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        from_rejection(rejection.status(), rejection.body_text())
+    }
+}
+
+/// What an error response carries to [`negotiate_errors`], which knows the
+/// request and picks the format.
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+        let status = self.status();
+        let code = self.code();
+        let message = match self {
+            AppError::BadRequest(msg)
+            | AppError::NotFound(msg)
+            | AppError::MethodNotAllowed(msg)
+            | AppError::NotAcceptable(msg)
+            | AppError::UnprocessableEntity(msg) => msg,
             AppError::InternalServerError(err) => {
                 tracing::error!("Internal server error: {:?}", err);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
+                "Internal server error".to_string()
             }
         };
-        
+
         let body = Json(json!({
-            "error": message
+            "error": message,
+            "code": code,
         }));
-        
-        (status, body).into_response()
+
+        let mut response = (status, body).into_response();
+        response.extensions_mut().insert(ErrorReport {
+            status,
+            code,
+            message,
+        });
+        response
+    }
+}
+
+/// How the client that made the request can show an error.
+enum ErrorFormat {
+    /// A toast patched into the page that ran the action.
+    Datastar,
+    /// A themed page for browser navigations.
+    Html,
+    /// `{"error", "code", "request_id"}` for everything else.
+    Json,
+}
+
+/// Whether the request is a browser navigation rather than an action or API call.
+pub fn is_page_request(headers: &HeaderMap) -> bool {
+    !DatastarRequest::is_present(headers)
+        && headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/html"))
+}
+
+impl ErrorFormat {
+    fn for_request(headers: &HeaderMap) -> Self {
+        if DatastarRequest::is_present(headers) {
+            ErrorFormat::Datastar
+        } else if is_page_request(headers) {
+            ErrorFormat::Html
+        } else {
+            ErrorFormat::Json
+        }
+    }
+}
+
+/// Middleware rendering [`AppError`] responses in the format the client asked
+/// for, tagged with the request id.
+pub async fn negotiate_errors(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let format = ErrorFormat::for_request(request.headers());
+    // The request is moved on, so keep what an error page's theme needs
+    let theme_headers = matches!(format, ErrorFormat::Html)
+        .then(|| client_hints::theme_headers(request.headers()));
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_default();
+
+    let original = next.run(request).await;
+    let Some(report) = original.extensions().get::<ErrorReport>().cloned() else {
+        return original;
+    };
+
+    tracing::warn!(
+        status = report.status.as_u16(),
+        code = report.code,
+        message = %report.message,
+        "Request failed"
+    );
+
+    let mut response = match format {
+        ErrorFormat::Datastar => TemplatePatch::new(ErrorToastTemplate {
+            message: report.message,
+            code: report.code,
+            request_id,
+        })
+        .selector("#toasts")
+        .mode(ElementPatchMode::Append)
+        .into_response(),
+        ErrorFormat::Html => ErrorPageTemplate {
+            title: state.settings.application.name.clone(),
+            environment: state.settings.application.environment.clone(),
+            theme: page_theme(&state, &theme_headers.unwrap_or_default()),
+            status: report.status.as_u16(),
+            message: report.message,
+            code: report.code,
+            request_id,
+        }
+        .into_response(),
+        ErrorFormat::Json => Json(json!({
+            "error": report.message,
+            "code": report.code,
+            "request_id": request_id,
+        }))
+        .into_response(),
+    };
+    *response.status_mut() = report.status;
+    // Keep headers like `Allow` on a 405, but not the JSON body's
+    for (name, value) in original.headers() {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            response.headers_mut().insert(name, value.clone());
+        }
     }
-}
\ No newline at end of file
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        request_id::REQUEST_ID_HEADER,
+        testing::{body_text, send},
+    };
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+
+    const REQUEST_ID: &str = "test-request-1";
+
+    fn request(uri: &str) -> axum::http::request::Builder {
+        Request::get(uri).header(REQUEST_ID_HEADER, REQUEST_ID)
+    }
+
+    #[tokio::test]
+    async fn datastar_actions_get_a_toast() {
+        let response = send(
+            request("/examples/theme/switch")
+                .header("datastar-request", "true")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let body = body_text(response).await;
+        assert!(body.contains("selector #toasts"), "{body}");
+        assert!(body.contains("missing field `theme`"), "{body}");
+        assert!(body.contains(&format!("bad_request · {REQUEST_ID}")), "{body}");
+    }
+
+    #[tokio::test]
+    async fn form_rejections_keep_their_status() {
+        let response = send(
+            Request::post("/examples/elements/submit-form")
+                .header(REQUEST_ID_HEADER, REQUEST_ID)
+                .header("datastar-request", "true")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from("name=x"))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = body_text(response).await;
+        assert!(body.contains(&format!("unprocessable_entity · {REQUEST_ID}")), "{body}");
+    }
+
+    #[tokio::test]
+    async fn page_requests_get_an_error_page() {
+        let response = send(
+            request("/theme/light.css?density=huge")
+                .header(header::ACCEPT, "text/html")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap();
+        assert!(content_type.starts_with("text/html"), "{content_type}");
+        let body = body_text(response).await;
+        assert!(body.contains("unknown variant `huge`"), "{body}");
+        assert!(body.contains("bad_request"), "{body}");
+        assert!(body.contains(REQUEST_ID), "{body}");
+    }
+
+    #[tokio::test]
+    async fn other_clients_get_json() {
+        let response = send(
+            request("/theme/light.css?density=huge")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(body["code"], "bad_request");
+        assert_eq!(body["request_id"], REQUEST_ID);
+        assert!(body["error"].as_str().unwrap().contains("unknown variant `huge`"));
+    }
+}
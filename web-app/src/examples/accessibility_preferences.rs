@@ -0,0 +1,76 @@
+//! @title Accessibility Preferences
+//! @description Reduced motion, more contrast and text size as server-generated CSS variables
+//! @category theming
+//! @tags cookies, css-variables, client-hints
+//! @order 7
+//! @route POST /examples/accessibility/update update
+//! @snippet html
+//! <div data-on-change="@post('/examples/accessibility/update')"
+//!      style="display: grid; gap: var(--size-3);">
+//!     <fieldset style="display: flex; gap: var(--size-3); border: none; padding: 0; margin: 0;">
+//!         <legend>Reduce motion</legend>
+//!         <label><input type="radio" name="reduced-motion" value="auto" data-bind="accessibility.reducedMotion"> Like my OS</label>
+//!         <label><input type="radio" name="reduced-motion" value="on" data-bind="accessibility.reducedMotion"> On</label>
+//!         <label><input type="radio" name="reduced-motion" value="off" data-bind="accessibility.reducedMotion"> Off</label>
+//!     </fieldset>
+//!     <fieldset style="display: flex; gap: var(--size-3); border: none; padding: 0; margin: 0;">
+//!         <legend>More contrast</legend>
+//!         <label><input type="radio" name="high-contrast" value="auto" data-bind="accessibility.highContrast"> Like my OS</label>
+//!         <label><input type="radio" name="high-contrast" value="on" data-bind="accessibility.highContrast"> On</label>
+//!         <label><input type="radio" name="high-contrast" value="off" data-bind="accessibility.highContrast"> Off</label>
+//!     </fieldset>
+//!     <label>Text size
+//!         <select data-bind="accessibility.fontScale">
+//!             <option value="90">90%</option>
+//!             <option value="100">100%</option>
+//!             <option value="110">110%</option>
+//!             <option value="125">125%</option>
+//!             <option value="150">150%</option>
+//!         </select>
+//!     </label>
+//!     <p style="color: var(--text-2); margin: 0; font-size: var(--font-size-0);">Kept in a cookie and applied on every page, including the card flip.</p>
+//! </div>
+//! @end
+
+use crate::{
+    accessibility::AccessibilityPreferences,
+    error::AppError,
+    handlers::page_accessibility_for,
+    hypermedia::{Signals, TemplatePatch},
+    preferences::ACCESSIBILITY_COOKIE,
+    templates::AccessibilityStylesTemplate,
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+/// `$accessibility`, seeded on every page from the cookie.
+#[derive(Deserialize)]
+pub struct AccessibilitySignals {
+    accessibility: AccessibilityPreferences,
+}
+
+/// Stores the preferences and swaps the page's accessibility styles.
+pub async fn update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Signals(signals): Signals<AccessibilitySignals>,
+) -> Result<Response, AppError> {
+    let preferences = signals
+        .accessibility
+        .validate()
+        .map_err(AppError::bad_request)?;
+    let styles = AccessibilityStylesTemplate {
+        accessibility: page_accessibility_for(preferences, &headers),
+    };
+
+    Ok((
+        state.cookies.set(ACCESSIBILITY_COOKIE, preferences.encode()),
+        TemplatePatch::new(styles),
+    )
+        .into_response())
+}
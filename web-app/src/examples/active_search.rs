@@ -1,6 +1,12 @@
 //! @title Active Search
 //! @description Search examples as you type with instant results
-//! @html_start
+//! @category search
+//! @tags sse, debounce
+//! @order 1
+//! @pinned
+//! @layout inline
+//! @route GET /examples/search search
+//! @snippet html
 //! <input 
 //!     type="text" 
 //!     placeholder="Search examples..." 
@@ -8,115 +14,97 @@
 //!     data-on-input__debounce.200ms="@get('/examples/search')"
 //!     class="search-input"
 //! >
-//! <!-- Search results replace the contents of #example-cards-container -->
-//! @html_end
+//! <!-- Search results replace the contents of #example-cards -->
+//! @end
 
-use crate::{error::AppError, templates::ExampleSearchResultsTemplate, syntax_highlight::highlight_code};
-use askama::Template;
-use axum::{
-    extract::{Query, State},
-    http::HeaderMap,
-    response::{IntoResponse, Sse},
+use crate::{
+    catalog::ExampleQuery,
+    hypermedia::{Patches, Signals, TemplatePatch},
+    search::{tokenize, SearchHit, SearchKind},
+    templates::{ExampleFacetsTemplate, ExampleSearchResultsTemplate, SearchResultsTemplate},
 };
+use axum::extract::State;
+use datastar::consts::ElementPatchMode;
 use serde::Deserialize;
-use serde_json::Value;
-use datastar::prelude::*;
-use async_stream::stream;
-use core::convert::Infallible;
 
-#[derive(Deserialize, Debug)]
-pub struct SearchRequestExtractor {
-    datastar: Option<String>,
-}
+/// How many ranked hits are listed above the example cards.
+const MAX_HITS: usize = 8;
 
-impl SearchRequestExtractor {
-    pub fn get_search_text(&self) -> String {
-        self.datastar
-            .as_ref()
-            .and_then(|json_str| serde_json::from_str::<Value>(json_str).ok())
-            .and_then(|data| data.get("search").and_then(|v| v.as_str()).map(String::from))
-            .unwrap_or_default()
-    }
+/// Signals sent by the search input and the facet chips.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct SearchSignals {
+    pub search: String,
+    pub category: String,
+    pub tag: String,
 }
 
-#[derive(Clone)]
-pub struct SearchResult {
-    pub title: String,
-    pub description: String,
+/// Example ids in rank order, or `None` when the text has no searchable
+/// terms (e.g. a single letter) and every example should stay visible.
+fn ranked_ids(text: &str, hits: &[SearchHit]) -> Option<Vec<String>> {
+    (!tokenize(text).is_empty()).then(|| {
+        hits.iter()
+            .filter(|hit| hit.kind == SearchKind::Example)
+            .map(|hit| hit.key.clone())
+            .collect()
+    })
 }
 
 pub async fn search(
-    State(_state): State<crate::AppState>,
-    headers: HeaderMap,
-    Query(extractor): Query<SearchRequestExtractor>,
-) -> Result<impl IntoResponse, AppError> {
-    if headers.get("datastar-request").is_none() {
-        return Err(AppError::not_found(
-            "This endpoint only serves HTML fragments",
-        ));
-    }
+    State(state): State<crate::AppState>,
+    Signals(signals): Signals<SearchSignals>,
+) -> Patches {
+    // Rank examples and docs, then combine the ranking with the selected facets
+    let text = signals.search.trim().to_string();
+    let hits = state.search.search(&text);
+    let query = ExampleQuery {
+        ranked_ids: ranked_ids(&text, &hits),
+        category: signals.category,
+        tag: signals.tag,
+    };
 
-    // Extract search text from datastar parameter
-    let query = extractor.get_search_text().to_lowercase();
-    
     // Log the search request
     tracing::info!(
-        search_query = %query,
-        is_empty = query.is_empty(),
+        search_query = %text,
+        category = %query.category,
+        tag = %query.tag,
         "Search request received"
     );
 
-    // Get all examples from generated data
-    let all_examples = crate::examples_gen::get_examples();
-
-    // Filter examples based on search query and add syntax highlighting
-    let results: Vec<_> = if query.is_empty() {
-        all_examples
-    } else {
-        all_examples
-            .into_iter()
-            .filter(|example| {
-                example.title.to_lowercase().contains(&query)
-                    || example.description.to_lowercase().contains(&query)
-                    || example.id.to_lowercase().contains(&query)
-            })
-            .collect()
-    }
-    .into_iter()
-    .map(|ex| {
-        let highlighted_html = highlight_code(&ex.html, "html").unwrap_or_else(|_| ex.html.clone());
-        crate::templates::ExampleWithHighlight {
-            id: ex.id,
-            title: ex.title,
-            description: ex.description,
-            html: ex.html,
-            highlighted_html,
-            backend_file: ex.backend_file,
-        }
-    })
-    .collect();
+    let results = state.examples.search(&query);
+    let facets = state.examples.facets(&query);
 
     // Log the search results
     tracing::info!(
         results_count = results.len(),
+        hits_count = hits.len(),
         "Returning search results"
     );
 
-    let template = ExampleSearchResultsTemplate { examples: results };
-    
-    // Render the template to HTML
-    let html = template.render().map_err(|e| AppError::InternalServerError(anyhow::anyhow!("Template error: {}", e)))?;
-    
-    // Wrap results in the example-cards div to maintain structure
-    let wrapped_html = format!(r#"<div id="example-cards">{}</div>"#, html);
-    
-    // Use datastar SDK to create proper SSE response with Inner mode
-    Ok(Sse::new(stream! {
-        let patch = PatchElements::new(wrapped_html)
-            .mode(datastar::consts::ElementPatchMode::Inner)
-            .selector("#example-cards-container");
-        
-        let sse_event = patch.write_as_axum_sse_event();
-        yield Ok::<_, Infallible>(sse_event);
-    }).into_response())
-}
\ No newline at end of file
+    // The cards are patched inside their container, the facet bar and the
+    // hit list are morphed by id
+    Patches::new()
+        .elements(
+            TemplatePatch::new(ExampleSearchResultsTemplate { examples: results })
+                .selector("#example-cards")
+                .mode(ElementPatchMode::Inner),
+        )
+        .elements(TemplatePatch::new(ExampleFacetsTemplate { facets }))
+        .elements(TemplatePatch::new(SearchResultsTemplate {
+            query: text,
+            total: hits.len(),
+            hits: hits.into_iter().take(MAX_HITS).collect(),
+        }))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_without_terms_keeps_every_example() {
+        assert_eq!(ranked_ids("", &[]), None);
+        assert_eq!(ranked_ids("a", &[]), None);
+        assert_eq!(ranked_ids(" - ? ", &[]), None);
+        assert_eq!(ranked_ids("sse", &[]), Some(Vec::new()));
+    }
+}
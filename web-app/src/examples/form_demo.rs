@@ -1,7 +1,12 @@
 //! @title Form Demo
 //! @description Form submission with hypermedia responses
-//! @html_start
-//! <form data-on-submit="@post('/examples/elements/submit-form', {contentType: 'form'})">
+//! @category forms
+//! @tags post, validation
+//! @order 3
+//! @route POST /examples/elements/submit-form submit_form
+//! @snippet html
+//! <form data-signals-form="{status: 'idle', error: ''}"
+//!       data-on-submit="@post('/examples/elements/submit-form', {contentType: 'form'})">
 //!     <div class="form-group">
 //!         <label for="name">Name:</label>
 //!         <input type="text" id="name" name="name" required>
@@ -10,16 +15,28 @@
 //!         <label for="value">Value:</label>
 //!         <input type="text" id="value" name="value" required>
 //!     </div>
+//!     <p class="form-error" data-show="$form.error" data-text="$form.error"></p>
 //!     <button type="submit">Submit</button>
 //! </form>
+//! <p class="form-status">Status: <strong data-text="$form.status"></strong></p>
+//! <p class="form-status" data-show="$form.first">First saved: <strong data-text="$form.first"></strong></p>
 //! <div id="form-response">
 //!     <!-- Response will be displayed here -->
 //! </div>
-//! @html_end
+//! @end
 
-use crate::{error::AppError, templates::FormResponseTemplate, AppState};
-use axum::{extract::State, http::HeaderMap, response::IntoResponse, Form};
-use serde::Deserialize;
+use crate::{
+    error::AppError,
+    extract::Form,
+    hypermedia::{Patches, SignalPatch, TemplatePatch},
+    templates::FormResponseTemplate,
+    AppState,
+};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct SubmitRequest {
@@ -27,18 +44,43 @@ pub struct SubmitRequest {
     pub value: String,
 }
 
+/// `$form`, owned by the server: the client only submits.
+#[derive(Serialize)]
+struct FormSignals {
+    form: FormStatus,
+}
+
+#[derive(Serialize)]
+struct FormStatus {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// `$form.first`, the name of the first entry saved on this page. Later
+/// saves send it too, but only the first one sets it.
+#[derive(Serialize)]
+struct FirstSaved {
+    form: FirstSavedName,
+}
+
+#[derive(Serialize)]
+struct FirstSavedName {
+    first: String,
+}
+
 pub async fn submit_form(
     State(_state): State<AppState>,
-    headers: HeaderMap,
     Form(payload): Form<SubmitRequest>,
-) -> Result<impl IntoResponse, AppError> {
-    if payload.name.is_empty() {
-        return Err(AppError::bad_request("Name cannot be empty"));
-    }
-    if headers.get("datastar-request").is_none() {
-        return Err(AppError::not_found(
-            "This endpoint only serves HTML fragments",
-        ));
+) -> Result<Response, AppError> {
+    if payload.name.trim().is_empty() {
+        let invalid = FormSignals {
+            form: FormStatus {
+                status: "invalid",
+                error: Some("Name cannot be empty".to_string()),
+            },
+        };
+        return Ok(SignalPatch::new(&invalid)?.into_response());
     }
 
     tracing::info!("Received submission: {} = {}", payload.name, payload.value);
@@ -46,7 +88,19 @@ pub async fn submit_form(
     let template = FormResponseTemplate {
         message: format!("Successfully processed: {}", payload.name),
     };
+    let saved = FormSignals {
+        form: FormStatus {
+            status: "saved",
+            error: None,
+        },
+    };
+    let first = FirstSaved {
+        form: FirstSavedName { first: payload.name },
+    };
 
-    let response = template.into_response();
-    Ok(response)
-}
\ No newline at end of file
+    Ok(Patches::new()
+        .elements(TemplatePatch::new(template))
+        .signals(SignalPatch::new(&saved)?.remove("form.error"))
+        .signals(SignalPatch::new(&first)?.only_if_missing())
+        .into_response())
+}
@@ -1,27 +1,24 @@
 //! @title Hypermedia Demo
 //! @description Dynamic content loading with Datastar
-//! @html_start
+//! @category fragments
+//! @tags get, fragments
+//! @order 2
+//! @route GET /examples/elements/get-items get_items
+//! @snippet html
 //! <button data-on-click="@get('/examples/elements/get-items')">
 //!     Fetch Data
 //! </button>
 //! <div id="data-content">
 //!     <p class="message">Click the button to load data</p>
 //! </div>
-//! @html_end
+//! @end
 
-use crate::{error::AppError, templates::DataItemsTemplate, AppState};
-use axum::{extract::State, http::HeaderMap, response::IntoResponse};
+use crate::{error::AppError, hypermedia::TemplatePatch, templates::DataItemsTemplate, AppState};
+use axum::{extract::State, response::IntoResponse};
 
 pub async fn get_items(
     State(_state): State<AppState>,
-    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
-    if headers.get("datastar-request").is_none() {
-        return Err(AppError::not_found(
-            "This endpoint only serves HTML fragments",
-        ));
-    }
-
     let template = DataItemsTemplate {
         message: "Hello from Rust Web Starter!".to_string(),
         timestamp: chrono::Utc::now(),
@@ -32,5 +29,5 @@ pub async fn get_items(
         ],
     };
 
-    Ok(template.into_response())
+    Ok(TemplatePatch::new(template).view_transition())
 }
\ No newline at end of file
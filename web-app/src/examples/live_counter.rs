@@ -0,0 +1,74 @@
+//! @title Live Counter
+//! @description One counter shared by every open tab, pushed over a long-lived stream
+//! @category streaming
+//! @tags sse, broadcast
+//! @order 5
+//! @route GET /examples/counter/updates updates
+//! @route POST /examples/counter/increment increment
+//! @snippet html
+//! <div data-signals-counter="{value: 0}"
+//!      data-on-load="@get('/examples/counter/updates')"
+//!      style="display: flex; align-items: center; gap: var(--size-3);">
+//!     <strong style="font-size: var(--font-size-5);" data-text="$counter.value"></strong>
+//!     <button data-on-click="@post('/examples/counter/increment')">+1</button>
+//!     <span style="color: var(--text-2);">Open this page in another tab and click.</span>
+//! </div>
+//! @end
+
+use crate::{
+    error::AppError,
+    hypermedia::{LastEventId, Patches, SignalPatch, Subscription},
+    AppState,
+};
+use axum::extract::State;
+use datastar::prelude::PatchSignals;
+use serde::Serialize;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+const TOPIC: &str = "live-counter";
+
+#[derive(Serialize)]
+struct CounterSignals {
+    counter: Counter,
+}
+
+#[derive(Serialize)]
+struct Counter {
+    value: i64,
+}
+
+/// The count is only written after its event is published, so a panic while
+/// the lock was held cannot leave it half-updated and the poison is ignored.
+fn lock(counter: &Mutex<i64>) -> MutexGuard<'_, i64> {
+    counter.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn counter_patch(value: i64) -> Result<PatchSignals, AppError> {
+    Ok(SignalPatch::new(&CounterSignals {
+        counter: Counter { value },
+    })?
+    .into())
+}
+
+/// Streams the current value, then every increment from any client. A
+/// reconnecting tab first gets the increments it missed.
+pub async fn updates(
+    State(state): State<AppState>,
+    last_event_id: Option<LastEventId>,
+) -> Result<Subscription, AppError> {
+    let subscription = state.hub.subscribe(TOPIC, last_event_id);
+    let current = counter_patch(*lock(&state.counter))?;
+    Ok(subscription.with_initial(current))
+}
+
+pub async fn increment(State(state): State<AppState>) -> Result<Patches, AppError> {
+    let mut count = lock(&state.counter);
+    let value = *count + 1;
+    let receivers = state.hub.publish(TOPIC, counter_patch(value)?);
+    *count = value;
+    drop(count);
+    tracing::debug!(value, receivers, "Counter incremented");
+
+    // Everyone, including this client, hears about it on their stream
+    Ok(Patches::new())
+}
@@ -1,9 +1,3 @@
-pub mod active_search;
-pub mod form_demo;
-pub mod hypermedia_demo;
-pub mod theme_switcher;
-
-pub use active_search::{search, SearchResult};
-pub use form_demo::submit_form;
-pub use hypermedia_demo::get_items;
-pub use theme_switcher::switch_theme;
\ No newline at end of file
+// Example modules and their routes are generated by build.rs from the `//!`
+// header of each file in this directory.
+include!("../../generated/examples_routes.rs");
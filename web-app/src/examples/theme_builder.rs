@@ -0,0 +1,175 @@
+//! @title Theme Builder
+//! @description Generate an accessible OKLCH theme from a seed hue or brand color
+//! @category theming
+//! @tags oklch, contrast, cookies
+//! @order 6
+//! @route GET /examples/theme-builder/preview preview
+//! @route GET /examples/theme-builder/brand from_brand
+//! @route POST /examples/theme-builder/save save
+//! @snippet html
+//! <div data-signals-builder="{hue: 250, vibrancy: 40, scheme: 'dark', brand: '#4f7be8'}"
+//!      data-on-load="@get('/examples/theme-builder/preview')"
+//!      style="display: grid; gap: var(--size-3);">
+//!     <label>Hue <strong data-text="$builder.hue"></strong>
+//!         <input type="range" min="0" max="359" data-bind="builder.hue"
+//!                data-on-input__debounce.100ms="@get('/examples/theme-builder/preview')">
+//!     </label>
+//!     <label>Vibrancy <strong data-text="$builder.vibrancy + '%'"></strong>
+//!         <input type="range" min="0" max="100" data-bind="builder.vibrancy"
+//!                data-on-input__debounce.100ms="@get('/examples/theme-builder/preview')">
+//!     </label>
+//!     <div style="display: flex; gap: var(--size-3);">
+//!         <label><input type="radio" name="builder-scheme" value="dark" data-bind="builder.scheme"
+//!                       data-on-change="@get('/examples/theme-builder/preview')"> Dark</label>
+//!         <label><input type="radio" name="builder-scheme" value="light" data-bind="builder.scheme"
+//!                       data-on-change="@get('/examples/theme-builder/preview')"> Light</label>
+//!     </div>
+//!     <label>Or start from a brand color
+//!         <input type="color" data-bind="builder.brand"
+//!                data-on-change="@get('/examples/theme-builder/brand')">
+//!     </label>
+//!     <div id="theme-builder-preview"></div>
+//!     <button data-on-click="@post('/examples/theme-builder/save')">Use this theme</button>
+//! </div>
+//! @end
+
+use crate::{
+    contrast::{self, color},
+    error::AppError,
+    examples::theme_switcher::ThemeSignals,
+    handlers::page_accessibility,
+    hypermedia::{Patches, SignalPatch, Signals, TemplatePatch},
+    preferences::{CUSTOM_THEME_COOKIE, THEME_COOKIE},
+    syntax_highlight::highlight_code,
+    templates::{PageTheme, ThemeBuilderPreviewTemplate, ThemeStylesTemplate},
+    theme::{
+        generator::{Seed, CUSTOM},
+        ColorScheme,
+    },
+    viewport::{ViewportContext, ViewportSignal},
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+const SAMPLE: &str = r#"/// Greets everyone on the list
+fn greet(names: &[&str]) -> usize {
+    for name in names {
+        println!("Hello, {name}!");
+    }
+    names.len() * 2
+}"#;
+
+static HIGHLIGHTED_SAMPLE: LazyLock<String> =
+    LazyLock::new(|| highlight_code(SAMPLE, "rust").unwrap_or_else(|_| SAMPLE.to_string()));
+
+/// `$builder`, set by the sliders, the scheme radios and the color picker.
+#[derive(Deserialize)]
+pub struct BuilderSignals {
+    builder: Builder,
+    #[serde(default)]
+    viewport: Option<ViewportSignal>,
+}
+
+#[derive(Deserialize)]
+struct Builder {
+    hue: u16,
+    vibrancy: u8,
+    scheme: ColorScheme,
+    #[serde(default)]
+    brand: String,
+}
+
+impl Builder {
+    fn seed(&self) -> Seed {
+        Seed::new(self.hue, self.vibrancy, self.scheme)
+    }
+}
+
+/// The sliders moved to match a brand color.
+#[derive(Serialize)]
+struct SeedSignals {
+    builder: SeedValues,
+}
+
+#[derive(Serialize)]
+struct SeedValues {
+    hue: u16,
+    vibrancy: u8,
+}
+
+/// The generated theme, scoped to the preview, with its contrast summary.
+fn preview_patch(seed: Seed) -> TemplatePatch<ThemeBuilderPreviewTemplate> {
+    let theme = seed.generate();
+    let report = contrast::check_themes([&theme]);
+    let lowest_contrast = report
+        .checks
+        .iter()
+        .map(|check| check.ratio)
+        .fold(f64::INFINITY, f64::min);
+    TemplatePatch::new(ThemeBuilderPreviewTemplate {
+        declarations: theme.declarations().to_string(),
+        sample: HIGHLIGHTED_SAMPLE.clone(),
+        checks: report.checks.len(),
+        lowest_contrast,
+        passed: report.passed(),
+    })
+}
+
+pub async fn preview(
+    Signals(signals): Signals<BuilderSignals>,
+) -> TemplatePatch<ThemeBuilderPreviewTemplate> {
+    preview_patch(signals.builder.seed())
+}
+
+/// Seeds the sliders from `$builder.brand`, then previews the result.
+pub async fn from_brand(Signals(signals): Signals<BuilderSignals>) -> Result<Patches, AppError> {
+    let builder = signals.builder;
+    let brand = color::parse(&builder.brand)
+        .map_err(|e| AppError::bad_request(format!("Invalid brand color: {}", e)))?;
+    let seed = Seed::from_color(brand, builder.scheme);
+    let signals = SignalPatch::new(&SeedSignals {
+        builder: SeedValues {
+            hue: seed.hue,
+            vibrancy: seed.vibrancy,
+        },
+    })?;
+    Ok(Patches::new()
+        .elements(preview_patch(seed))
+        .signals(signals))
+}
+
+/// Stores the seed and switches the visitor to the custom theme. Only the
+/// seed is stored in the cookie; the theme generated from it is cached for
+/// the pages and stylesheet requests that follow.
+pub async fn save(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Signals(signals): Signals<BuilderSignals>,
+) -> Result<Response, AppError> {
+    let seed = signals.builder.seed();
+    let viewport = ViewportContext::from_headers(&headers).with_signal(signals.viewport);
+    let styles = ThemeStylesTemplate {
+        theme: PageTheme {
+            stylesheet: state
+                .custom_themes
+                .get(seed)
+                .stylesheet_url(viewport.density()),
+            preference: CUSTOM.to_string(),
+            accessibility: page_accessibility(&state, &headers),
+        },
+    };
+    let signals = SignalPatch::new(&ThemeSignals { theme: CUSTOM })?;
+
+    Ok((
+        state.cookies.set(CUSTOM_THEME_COOKIE, seed.encode()),
+        state.cookies.set(THEME_COOKIE, CUSTOM),
+        TemplatePatch::new(styles).signals(signals),
+    )
+        .into_response())
+}
@@ -1,104 +1,84 @@
 //! @title Server-Side Theme Switcher
 //! @description Change themes with server-generated CSS variables
-//! @html_start
-//! <div style="display: flex; flex-wrap: wrap; gap: var(--size-3); align-items: center;">
-//!     <label style="display: flex; align-items: center; gap: var(--size-2); cursor: pointer;">
-//!         <input
-//!             type="radio"
-//!             name="theme"
-//!             value="light"
-//!             data-on-click="$theme = 'light'; @get('/examples/theme/switch?theme=light')"
-//!             data-attr-checked="$theme === 'light'">
-//!         <span>☀️ Light</span>
-//!     </label>
-//!     <label style="display: flex; align-items: center; gap: var(--size-2); cursor: pointer;">
-//!         <input
-//!             type="radio"
-//!             name="theme"
-//!             value="dim"
-//!             data-on-click="$theme = 'dim'; @get('/examples/theme/switch?theme=dim')"
-//!             data-attr-checked="$theme === 'dim'">
-//!         <span> 🌚 Dim</span>
-//!     </label>
-//!     <label style="display: flex; align-items: center; gap: var(--size-2); cursor: pointer;">
-//!         <input
-//!             type="radio"
-//!             name="theme"
-//!             value="dark"
-//!             data-on-click="$theme = 'dark'; @get('/examples/theme/switch?theme=dark')"
-//!             data-attr-checked="$theme === 'dark'">
-//!         <span>🌙 Dark</span>
-//!     </label>
-//!     <label style="display: flex; align-items: center; gap: var(--size-2); cursor: pointer;">
-//!         <input
-//!             type="radio"
-//!             name="theme"
-//!             value="grape"
-//!             data-on-click="$theme = 'grape'; @get('/examples/theme/switch?theme=grape')"
-//!             data-attr-checked="$theme === 'grape'">
-//!         <span>🍇 Grape</span>
-//!     </label>
-//! </div>
+//! @category theming
+//! @tags cookies, css-variables
+//! @order 4
+//! @route GET /examples/theme/switch switch_theme
+//! @route GET /examples/theme/options theme_options
+//! @snippet html
+//! <div id="theme-options" data-on-load="@get('/examples/theme/options')"></div>
 //! <div style="margin-top: var(--size-4); padding: var(--size-3); background: var(--surface-2); border-radius: var(--radius-2);">
 //!     <p style="color: var(--text-1); margin: 0;">Current theme: <strong data-text="$theme"></strong></p>
 //!     <p style="color: var(--text-2); margin: 0; margin-top: var(--size-2); font-size: var(--font-size-0);">This theme is persisted and sent with every request!</p>
 //! </div>
-//! @html_end
+//! @end
 
 use crate::{
     error::AppError,
-    theme::{get_syntax_highlighting_variables, get_theme_variables, Theme},
+    extract::Query,
+    handlers::{custom_seed, page_theme_for},
+    hypermedia::{SignalPatch, TemplatePatch},
+    preferences::THEME_COOKIE,
+    theme::generator::CUSTOM,
+    viewport::ViewportContext,
+    templates::{ThemeOptionsTemplate, ThemeStylesTemplate},
+    AppState,
 };
 use axum::{
-    extract::Query,
-    http::{header, HeaderMap},
-    response::{Html, IntoResponse, Response},
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct ThemeQuery {
     pub theme: String,
 }
 
+/// The server decides which theme is active; the radios only ask for one.
+#[derive(Serialize)]
+pub(crate) struct ThemeSignals<'a> {
+    pub theme: &'a str,
+}
+
+/// The radios: `auto`, then one per theme in `config/themes`, then the
+/// visitor's custom theme if they saved one in the theme builder.
+pub async fn theme_options(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> TemplatePatch<ThemeOptionsTemplate> {
+    let themes = std::iter::once(state.themes.auto())
+        .chain(state.themes.all())
+        .cloned()
+        .chain(custom_seed(&state, &headers).map(|seed| (*state.custom_themes.get(seed)).clone()))
+        .collect();
+    TemplatePatch::new(ThemeOptionsTemplate { themes })
+}
+
 pub async fn switch_theme(
+    State(state): State<AppState>,
     Query(params): Query<ThemeQuery>,
     headers: HeaderMap,
+    viewport: ViewportContext,
 ) -> Result<Response, AppError> {
-    if headers.get("datastar-request").is_none() {
-        return Err(AppError::not_found(
-            "This endpoint only serves Datastar responses",
-        ));
-    }
-
-    let theme = Theme::from_str(&params.theme).unwrap_or(Theme::Light);
-    let theme_css = get_theme_variables(&theme);
-    let syntax_css = get_syntax_highlighting_variables(&theme);
-
-    let html = format!(
-        r#"<style id="theme">
-        :root {{
-            {}
-        }}
-    </style>
-    <style id="syntax-highlighting">
-        :root {{
-            {}
-        }}
-    </style>"#,
-        theme_css, syntax_css
-    );
+    let preference = if params.theme == CUSTOM && custom_seed(&state, &headers).is_some() {
+        CUSTOM
+    } else {
+        state
+            .themes
+            .get(&params.theme)
+            .map(|theme| theme.name.as_str())
+            .ok_or_else(|| AppError::bad_request(format!("Unknown theme: {}", params.theme)))?
+    };
+    let styles = ThemeStylesTemplate {
+        theme: page_theme_for(&state, preference, &headers, &viewport),
+    };
+    let signals = SignalPatch::new(&ThemeSignals { theme: preference })?;
 
-    let mut response = Html(html).into_response();
-
-    let cookie_value = format!(
-        "theme={}; Path=/; Max-Age=31536000; SameSite=Lax",
-        params.theme
-    );
-    response
-        .headers_mut()
-        .insert(header::SET_COOKIE, cookie_value.parse().unwrap());
-
-    Ok(response)
+    Ok((
+        state.cookies.set(THEME_COOKIE, preference),
+        TemplatePatch::new(styles).signals(signals),
+    )
+        .into_response())
 }
-
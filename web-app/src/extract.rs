@@ -0,0 +1,18 @@
+use crate::error::AppError;
+use axum::extract::{FromRequest, FromRequestParts};
+
+/// Axum's `Query`, rejecting with [`AppError`] so a malformed query gets the
+/// negotiated error response rather than axum's plain text.
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(AppError))]
+pub struct Query<T>(pub T);
+
+/// Axum's `Form`, rejecting with [`AppError`].
+#[derive(Debug, FromRequest)]
+#[from_request(via(axum::extract::Form), rejection(AppError))]
+pub struct Form<T>(pub T);
+
+/// Axum's `Path`, rejecting with [`AppError`].
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct Path<T>(pub T);
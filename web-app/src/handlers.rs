@@ -1,130 +1,453 @@
 use crate::{
-    syntax_highlight::highlight_code,
-    templates::{BackendCodeTemplate, ExamplesTemplate, ExampleWithHighlight, IndexTemplate},
-    theme::{get_syntax_highlighting_variables, get_theme_variables, Theme},
+    accessibility::AccessibilityPreferences,
+    catalog::ExampleQuery,
+    client_hints,
+    error::{is_page_request, AppError},
+    extract::{Path, Query},
+    hypermedia::{hub::TopicMetrics, TemplatePatch},
+    preferences::{ACCESSIBILITY_COOKIE, CUSTOM_THEME_COOKIE, THEME_COOKIE},
+    templates::{
+        BackendCodeTemplate, DocsPageTemplate, ExamplePageTemplate, ExamplesTemplate,
+        IndexTemplate, NotFoundTemplate, PageAccessibility, PageTheme, ThemeStylesTemplate,
+    },
+    theme::generator::{Seed, CUSTOM},
+    viewport::{Density, ViewportContext},
     AppState,
 };
 use axum::{
-    extract::{Path, State},
+    extract::State,
     http::header,
-    http::HeaderMap,
-    response::IntoResponse,
+    http::{HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    Json,
 };
-use std::fs;
+use serde::Deserialize;
+use std::sync::atomic::Ordering;
 
-pub use crate::examples::{
-    get_items, search, submit_form, switch_theme, SearchResult,
-};
+// The visitor's theme preference from the signed cookie, or the configured default
+pub fn theme_preference<'a>(state: &'a AppState, headers: &HeaderMap) -> &'a str {
+    let cookie = state.cookies.get(headers, THEME_COOKIE);
+    if cookie.as_deref() == Some(CUSTOM) && custom_seed(state, headers).is_some() {
+        return CUSTOM;
+    }
+    state.themes.preference(cookie.as_deref())
+}
+
+// The seed of the visitor's saved custom theme, if they built one
+pub fn custom_seed(state: &AppState, headers: &HeaderMap) -> Option<Seed> {
+    state
+        .cookies
+        .get(headers, CUSTOM_THEME_COOKIE)
+        .and_then(|seed| Seed::decode(&seed))
+}
+
+// The theme to render: the cookie's choice, or for `auto` the OS preference,
+// at the density of the viewport in the client hints
+pub fn page_theme(state: &AppState, headers: &HeaderMap) -> PageTheme {
+    let viewport = ViewportContext::from_headers(headers);
+    page_theme_for(state, theme_preference(state, headers), headers, &viewport)
+}
+
+// The theme to render for a known `preference` and viewport, e.g. ones a
+// Datastar action just sent
+pub fn page_theme_for(
+    state: &AppState,
+    preference: &str,
+    headers: &HeaderMap,
+    viewport: &ViewportContext,
+) -> PageTheme {
+    let density = viewport.density();
+    let stylesheet = match custom_seed(state, headers).filter(|_| preference == CUSTOM) {
+        Some(seed) => state.custom_themes.get(seed).stylesheet_url(density),
+        None => state
+            .themes
+            .pick(preference, client_hints::prefers_color_scheme(headers))
+            .stylesheet_url(density),
+    };
+    PageTheme {
+        stylesheet,
+        preference: preference.to_string(),
+        accessibility: page_accessibility(state, headers),
+    }
+}
 
-// Helper function to extract theme from cookies
-fn extract_theme_from_headers(headers: &HeaderMap) -> Theme {
-    // Try to get theme from cookie
-    if let Some(cookie_header) = headers.get(header::COOKIE) {
-        if let Ok(cookie_str) = cookie_header.to_str() {
-            // Parse cookies to find theme
-            for cookie in cookie_str.split(';') {
-                let parts: Vec<&str> = cookie.trim().split('=').collect();
-                if parts.len() == 2 && parts[0] == "theme" {
-                    return Theme::from_str(parts[1]).unwrap_or(Theme::default());
-                }
-            }
+// The visitor's accessibility preferences from the signed cookie, or the defaults
+pub fn accessibility_preferences(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> AccessibilityPreferences {
+    state
+        .cookies
+        .get(headers, ACCESSIBILITY_COOKIE)
+        .and_then(|value| AccessibilityPreferences::decode(&value))
+        .unwrap_or_default()
+}
+
+// The accessibility styles to render: the cookie's choices, with `auto`
+// settled by client hints where the browser sent them
+pub fn page_accessibility(state: &AppState, headers: &HeaderMap) -> PageAccessibility {
+    page_accessibility_for(accessibility_preferences(state, headers), headers)
+}
+
+// The accessibility styles for known `preferences`, e.g. ones just chosen
+pub fn page_accessibility_for(
+    preferences: AccessibilityPreferences,
+    headers: &HeaderMap,
+) -> PageAccessibility {
+    PageAccessibility {
+        preferences,
+        stylesheet: preferences.resolve(headers).stylesheet(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StylesheetQuery {
+    v: Option<String>,
+    seed: Option<String>,
+    density: Option<Density>,
+}
+
+// Theme variables as a stylesheet, `/theme/<name>.css`, or for generated
+// themes `/theme/custom.css?seed=<seed>`, with `?density=` variables
+pub async fn theme_stylesheet(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    Query(query): Query<StylesheetQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let unknown = || AppError::not_found(format!("Unknown theme stylesheet: {}", file));
+    let generated;
+    let theme = match file.strip_suffix(".css") {
+        Some(CUSTOM) => {
+            let seed = query
+                .seed
+                .as_deref()
+                .and_then(Seed::decode)
+                .ok_or_else(unknown)?;
+            generated = state.custom_themes.get(seed);
+            &*generated
         }
+        name => name
+            .and_then(|name| state.themes.get(name))
+            .ok_or_else(unknown)?,
+    };
+
+    let density = query.density;
+    let etag = format!(
+        "\"{}-{}\"",
+        theme.version(),
+        density.map_or("auto", |density| density.as_str())
+    );
+    // Versioned URLs never change content; anything else must revalidate
+    let cache_control = if query.v.as_deref() == Some(theme.version()) {
+        "public, max-age=31536000, immutable"
+    } else {
+        "public, no-cache"
+    };
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, cache_control.to_string()),
+    ];
+
+    let fresh = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag)
+        });
+    if fresh {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
     }
-    Theme::default()
+
+    Ok((
+        cache_headers,
+        [(header::CONTENT_TYPE, "text/css; charset=utf-8")],
+        theme.stylesheet_for(density),
+    )
+        .into_response())
 }
 
 // Main index page handler
 pub async fn index(State(state): State<AppState>, headers: HeaderMap) -> IndexTemplate {
-    // Extract theme from request (will be in signals once we handle them)
-    let theme = extract_theme_from_headers(&headers);
-    let theme_css = get_theme_variables(&theme);
+    // Extract theme from request
+    let theme = page_theme(&state, &headers);
 
     IndexTemplate {
         title: state.settings.application.name.clone(),
         environment: state.settings.application.environment.clone(),
-        theme_css,
+        theme,
     }
 }
 
 // Examples page handler
 pub async fn examples(State(state): State<AppState>, headers: HeaderMap) -> ExamplesTemplate {
     // Extract theme from request
-    let theme = extract_theme_from_headers(&headers);
-    let theme_css = get_theme_variables(&theme);
-    let syntax_css = get_syntax_highlighting_variables(&theme);
-    
-    // Get generated examples data and add syntax highlighting
-    let raw_examples = crate::examples_gen::get_examples();
-    let examples: Vec<ExampleWithHighlight> = raw_examples
-        .into_iter()
-        .map(|ex| {
-            let highlighted_html = highlight_code(&ex.html, "html").unwrap_or_else(|_| ex.html.clone());
-            ExampleWithHighlight {
-                id: ex.id,
-                title: ex.title,
-                description: ex.description,
-                html: ex.html,
-                highlighted_html,
-                backend_file: ex.backend_file,
-            }
-        })
-        .collect();
-    
+    let theme = page_theme(&state, &headers);
+
+    let featured = state.examples.featured().cloned().collect();
+    let examples = state.examples.unpinned().cloned().collect();
+    let facets = state.examples.facets(&ExampleQuery::default());
+
     ExamplesTemplate {
         title: state.settings.application.name.clone(),
         environment: state.settings.application.environment.clone(),
-        theme_css,
-        syntax_css,
+        theme,
+        featured,
         examples,
+        facets,
+    }
+}
+
+// Standalone, deep-linkable page for a single example
+pub async fn example_page(
+    State(state): State<AppState>,
+    Path(example_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let theme = page_theme(&state, &headers);
+
+    let Some(example) = state.examples.get(&example_id) else {
+        return not_found_page(
+            &state,
+            &headers,
+            &example_id,
+            &format!("There is no example called \"{}\".", example_id),
+        );
+    };
+
+    ExamplePageTemplate {
+        title: state.settings.application.name.clone(),
+        environment: state.settings.application.environment.clone(),
+        theme,
+        example: example.clone(),
+    }
+    .into_response()
+}
+
+// Datastar reference landing: the first bundled docs page
+pub async fn docs_index(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    match state.docs.landing_url() {
+        Some(url) => Redirect::to(url).into_response(),
+        None => not_found_page(
+            &state,
+            &headers,
+            "/docs",
+            "The Datastar docs are not bundled with this build.",
+        ),
+    }
+}
+
+// Bundled Datastar reference page rendered from ref/datastar-docs
+pub async fn docs_page(
+    State(state): State<AppState>,
+    Path((section, slug)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(page) = state.docs.get(&section, &slug) else {
+        return not_found_page(
+            &state,
+            &headers,
+            &format!("/docs/{}/{}", section, slug),
+            &format!("There is no docs page at {}/{}.", section, slug),
+        );
+    };
+
+    DocsPageTemplate {
+        title: state.settings.application.name.clone(),
+        environment: state.settings.application.environment.clone(),
+        theme: page_theme(&state, &headers),
+        sections: state.docs.sections().to_vec(),
+        current_url: page.url(),
+        page: page.clone(),
     }
+    .into_response()
+}
+
+// Router fallback for paths no route matches
+pub async fn not_found(State(state): State<AppState>, uri: Uri, headers: HeaderMap) -> Response {
+    if !is_page_request(&headers) {
+        count_miss(&state, uri.path(), None);
+        return AppError::not_found(format!("Nothing is served at {}", uri.path())).into_response();
+    }
+    not_found_page(
+        &state,
+        &headers,
+        uri.path(),
+        "There is nothing at this address.",
+    )
+}
+
+// Router fallback for known paths requested with the wrong method
+pub async fn method_not_allowed(method: Method, uri: Uri) -> AppError {
+    AppError::method_not_allowed(format!("{} is not supported on {}", method, uri.path()))
+}
+
+/// Themed 404 pointing at the example or docs page closest to `path`.
+fn not_found_page(state: &AppState, headers: &HeaderMap, path: &str, message: &str) -> Response {
+    let suggestion = state.search.suggest(path);
+    count_miss(state, path, suggestion.as_ref().map(|hit| hit.url.as_str()));
+
+    let template = NotFoundTemplate {
+        title: state.settings.application.name.clone(),
+        environment: state.settings.application.environment.clone(),
+        theme: page_theme(state, headers),
+        message: message.to_string(),
+        suggestion,
+    };
+    (StatusCode::NOT_FOUND, template).into_response()
+}
+
+fn count_miss(state: &AppState, path: &str, suggestion: Option<&str>) {
+    let misses = state.not_found.fetch_add(1, Ordering::Relaxed) + 1;
+    tracing::info!(path, suggestion, misses, "Not found");
 }
 
 // Handler to serve backend code for examples
-pub async fn get_example_code(Path(example_id): Path<String>, headers: HeaderMap) -> impl IntoResponse {
-    // Only serve fragments for Datastar requests
-    if headers.get("datastar-request").is_none() {
-        return BackendCodeTemplate {
-            example_id: example_id.clone(),
-            code: "// This endpoint only serves Datastar fragments".to_string(),
-        }.into_response();
-    }
-    
-    let file_path = format!("src/examples/{}.rs", example_id.replace('-', "_"));
-    
-    let raw_code = match fs::read_to_string(&file_path) {
-        Ok(content) => {
-            // Filter out the metadata comment lines
-            content
-                .lines()
-                .skip_while(|line| line.starts_with("//!"))
-                .collect::<Vec<&str>>()
-                .join("\n")
-                .trim_start()
-                .to_string()
+pub async fn get_example_code(
+    State(state): State<AppState>,
+    Path(example_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let example = state
+        .examples
+        .get(&example_id)
+        .ok_or_else(|| AppError::not_found(format!("Unknown example: {}", example_id)))?;
+
+    Ok(TemplatePatch::new(BackendCodeTemplate {
+        example_id,
+        code: example.highlighted_source.clone(),
+    }))
+}
+
+// Re-links the theme stylesheet at the density of `$viewport`, which pages
+// send when the window is resized
+pub async fn viewport_density(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    viewport: ViewportContext,
+) -> TemplatePatch<ThemeStylesTemplate> {
+    let preference = theme_preference(&state, &headers);
+    TemplatePatch::new(ThemeStylesTemplate {
+        theme: page_theme_for(&state, preference, &headers, &viewport),
+    })
+}
+
+// Subscriber counts and delivery stats for every live-stream topic
+pub async fn hub_metrics(State(state): State<AppState>) -> Json<Vec<TopicMetrics>> {
+    Json(state.hub.metrics())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client_hints::PREFERS_COLOR_SCHEME,
+        testing::{send, state},
+    };
+    use axum::{
+        body::Body,
+        http::{Request, Response},
+    };
+
+    async fn stylesheet(uri: &str, if_none_match: Option<&str>) -> Response<Body> {
+        let mut request = Request::get(uri);
+        if let Some(tags) = if_none_match {
+            request = request.header(header::IF_NONE_MATCH, tags);
         }
-        Err(e) => {
-            // Try alternative path from workspace root
-            let alt_path = format!("web-app/src/examples/{}.rs", example_id.replace('-', "_"));
-            match fs::read_to_string(&alt_path) {
-                Ok(content) => {
-                    content
-                        .lines()
-                        .skip_while(|line| line.starts_with("//!"))
-                        .collect::<Vec<&str>>()
-                        .join("\n")
-                        .trim_start()
-                        .to_string()
-                }
-                Err(_) => format!("// Example code not found\n// Tried: {}\n// Alt: {}\n// Error: {}", file_path, alt_path, e),
-            }
+        send(request.body(Body::empty()).unwrap()).await
+    }
+
+    fn header(response: &Response<Body>, name: header::HeaderName) -> &str {
+        response.headers()[name].to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn etag_is_the_version_and_density() {
+        let version = state().themes.get("dark").unwrap().version().to_string();
+        let response = stylesheet("/theme/dark.css", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            header(&response, header::ETAG),
+            format!("\"{version}-auto\"")
+        );
+
+        let response = stylesheet("/theme/dark.css?density=compact", None).await;
+        assert_eq!(
+            header(&response, header::ETAG),
+            format!("\"{version}-compact\"")
+        );
+    }
+
+    #[tokio::test]
+    async fn matching_if_none_match_is_not_modified() {
+        let etag = header(&stylesheet("/theme/dark.css", None).await, header::ETAG).to_string();
+
+        for tags in [
+            etag.as_str(),
+            &format!("W/{etag}"),
+            &format!("\"other\", {etag}"),
+            "*",
+        ] {
+            let response = stylesheet("/theme/dark.css", Some(tags)).await;
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{tags}");
+            assert_eq!(header(&response, header::ETAG), etag);
         }
-    };
-    
-    // Apply syntax highlighting
-    let code = highlight_code(&raw_code, "rust").unwrap_or(raw_code);
-    
-    BackendCodeTemplate {
-        example_id,
-        code,
-    }.into_response()
+
+        let response = stylesheet("/theme/dark.css?density=compact", Some(&etag)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn only_the_current_version_is_immutable() {
+        let version = state().themes.get("dark").unwrap().version().to_string();
+        let response = stylesheet(&format!("/theme/dark.css?v={version}"), None).await;
+        assert_eq!(
+            header(&response, header::CACHE_CONTROL),
+            "public, max-age=31536000, immutable"
+        );
+
+        for uri in ["/theme/dark.css", "/theme/dark.css?v=0000000000000000"] {
+            let response = stylesheet(uri, None).await;
+            assert_eq!(
+                header(&response, header::CACHE_CONTROL),
+                "public, no-cache",
+                "{uri}"
+            );
+        }
+    }
+
+    /// Request headers with the signed theme cookie for `theme` and the
+    /// color scheme `hint`.
+    fn theme_request(theme: Option<&str>, hint: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(theme) = theme {
+            let response = state().cookies.set(THEME_COOKIE, theme).into_response();
+            let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+            let pair = set_cookie.split(';').next().unwrap();
+            headers.insert(header::COOKIE, pair.parse().unwrap());
+        }
+        headers.insert(PREFERS_COLOR_SCHEME, hint.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn theme_cookie_overrides_the_hint() {
+        let state = state();
+        let page = page_theme(&state, &theme_request(Some("dark"), "light"));
+        assert_eq!(page.preference, "dark");
+        assert!(
+            page.stylesheet.starts_with("/theme/dark.css?"),
+            "{}",
+            page.stylesheet
+        );
+
+        let page = page_theme(&state, &theme_request(None, "dark"));
+        assert_eq!(page.preference, "auto");
+        assert!(
+            page.stylesheet.starts_with("/theme/dark.css?"),
+            "{}",
+            page.stylesheet
+        );
+    }
 }
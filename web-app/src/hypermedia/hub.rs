@@ -0,0 +1,316 @@
+use crate::hypermedia::stream::{event_stream, LastEventId};
+use axum::response::{IntoResponse, Response};
+use datastar::prelude::DatastarEvent;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// In-process pub/sub for Datastar events.
+///
+/// Each subscriber gets its own bounded buffer. A subscriber that lets its
+/// buffer fill up is disconnected rather than allowed to hold back the topic
+/// or grow without bound. Its stream just ends, and Datastar only retries
+/// streams that fail, so the page stays stale until it subscribes again.
+///
+/// Published events get ids `<epoch>-<sequence>` and the last `history` of
+/// them are kept per topic, so a reconnecting client that sends
+/// `Last-Event-ID` gets what it missed replayed. The epoch changes on every
+/// restart, which keeps ids from an earlier process from matching.
+pub struct Hub {
+    buffer: usize,
+    history: usize,
+    epoch: u64,
+    topics: Topics,
+}
+
+type Topics = Arc<Mutex<BTreeMap<String, Topic>>>;
+
+#[derive(Default)]
+struct Topic {
+    next_subscriber: u64,
+    subscribers: Vec<(u64, mpsc::Sender<Arc<DatastarEvent>>)>,
+    /// Recent events with their sequence numbers, oldest first.
+    history: VecDeque<(u64, Arc<DatastarEvent>)>,
+    published: u64,
+    delivered: u64,
+    replayed: u64,
+    slow_consumers: u64,
+}
+
+/// Counters for one topic since startup.
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicMetrics {
+    pub topic: String,
+    pub subscribers: usize,
+    pub published: u64,
+    pub delivered: u64,
+    /// Events resent to reconnecting subscribers from the history.
+    pub replayed: u64,
+    /// Subscribers disconnected because their buffer was full.
+    pub slow_consumers: u64,
+}
+
+impl Hub {
+    /// `buffer` is the number of events each subscriber may fall behind by,
+    /// `history` the number of events kept per topic for replay.
+    pub fn new(buffer: usize, history: usize) -> Self {
+        let epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        Self {
+            buffer: buffer.max(1),
+            history,
+            epoch,
+            topics: Topics::default(),
+        }
+    }
+
+    /// Subscribes to `topic`. With the `Last-Event-ID` of a reconnecting
+    /// client, the events it missed are queued first if the history still
+    /// holds all of them.
+    pub fn subscribe(&self, topic: &str, last_event_id: Option<LastEventId>) -> Subscription {
+        let (sender, receiver) = mpsc::channel(self.buffer);
+        let mut topics = self.topics.lock().unwrap();
+        let entry = topics.entry(topic.to_string()).or_default();
+        let id = entry.next_subscriber;
+        entry.next_subscriber += 1;
+        entry.subscribers.push((id, sender));
+
+        let mut replay = Vec::new();
+        if let Some(LastEventId(last_event_id)) = last_event_id {
+            match self.sequence(&last_event_id) {
+                // Resumable when nothing between the client's last event and
+                // the oldest one kept has been evicted
+                Some(last)
+                    if entry
+                        .history
+                        .front()
+                        .is_none_or(|(oldest, _)| *oldest <= last + 1) =>
+                {
+                    replay.extend(
+                        entry
+                            .history
+                            .iter()
+                            .filter(|(sequence, _)| *sequence > last)
+                            .map(|(_, event)| event.clone()),
+                    );
+                    entry.replayed += replay.len() as u64;
+                }
+                _ => tracing::debug!(
+                    topic,
+                    last_event_id,
+                    "Last-Event-ID is no longer in the history, not replaying"
+                ),
+            }
+        }
+
+        Subscription {
+            topics: self.topics.clone(),
+            topic: topic.to_string(),
+            id,
+            replay,
+            initial: Vec::new(),
+            receiver,
+        }
+    }
+
+    /// The sequence number of an event id from this process.
+    fn sequence(&self, event_id: &str) -> Option<u64> {
+        let (epoch, sequence) = event_id.split_once('-')?;
+        if epoch.parse::<u64>().ok()? != self.epoch {
+            return None;
+        }
+        sequence.parse().ok()
+    }
+
+    /// Sends `event` to every current subscriber of `topic` and returns how
+    /// many received it.
+    pub fn publish(&self, topic: &str, event: impl Into<DatastarEvent>) -> usize {
+        let mut topics = self.topics.lock().unwrap();
+        let entry = topics.entry(topic.to_string()).or_default();
+        entry.published += 1;
+
+        let sequence = entry.published;
+        let mut event = event.into();
+        event.id = Some(format!("{}-{}", self.epoch, sequence));
+        let event = Arc::new(event);
+        if self.history > 0 {
+            if entry.history.len() == self.history {
+                entry.history.pop_front();
+            }
+            entry.history.push_back((sequence, event.clone()));
+        }
+
+        let mut delivered = 0;
+        let mut slow = 0;
+        entry
+            .subscribers
+            .retain(|(_, sender)| match sender.try_send(event.clone()) {
+                Ok(()) => {
+                    delivered += 1;
+                    true
+                }
+                Err(TrySendError::Full(_)) => {
+                    slow += 1;
+                    false
+                }
+                Err(TrySendError::Closed(_)) => false,
+            });
+        entry.delivered += delivered;
+        entry.slow_consumers += slow;
+
+        if slow > 0 {
+            tracing::warn!(topic, slow_consumers = slow, "Disconnected slow subscribers");
+        }
+        delivered as usize
+    }
+
+    pub fn metrics(&self) -> Vec<TopicMetrics> {
+        self.topics
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, topic)| TopicMetrics {
+                topic: name.clone(),
+                subscribers: topic.subscribers.len(),
+                published: topic.published,
+                delivered: topic.delivered,
+                replayed: topic.replayed,
+                slow_consumers: topic.slow_consumers,
+            })
+            .collect()
+    }
+}
+
+/// A live subscription to one topic. Dropping it unsubscribes.
+///
+/// As a response it holds an SSE stream open and forwards every event
+/// published to the topic until the client goes away or falls behind.
+pub struct Subscription {
+    topics: Topics,
+    topic: String,
+    id: u64,
+    /// Missed events from the history, sent first.
+    replay: Vec<Arc<DatastarEvent>>,
+    /// Sent before anything newly published, e.g. the current state.
+    initial: Vec<DatastarEvent>,
+    receiver: mpsc::Receiver<Arc<DatastarEvent>>,
+}
+
+impl Subscription {
+    /// Queues `event` ahead of newly published events. Subscribe first and read
+    /// the state afterwards, so no update falls between the two.
+    pub fn with_initial(mut self, event: impl Into<DatastarEvent>) -> Self {
+        self.initial.push(event.into());
+        self
+    }
+
+    /// The next published event, or `None` once the hub dropped this subscriber.
+    pub async fn recv(&mut self) -> Option<Arc<DatastarEvent>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Ok(mut topics) = self.topics.lock() {
+            if let Some(topic) = topics.get_mut(&self.topic) {
+                topic.subscribers.retain(|(id, _)| *id != self.id);
+            }
+        }
+    }
+}
+
+impl IntoResponse for Subscription {
+    fn into_response(mut self) -> Response {
+        let replay = std::mem::take(&mut self.replay);
+        let initial = std::mem::take(&mut self.initial);
+        let events = replay
+            .iter()
+            .map(|event| event.write_as_axum_sse_event())
+            .chain(initial.iter().map(DatastarEvent::write_as_axum_sse_event))
+            .collect();
+        event_stream(events, Some(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datastar::prelude::PatchSignals;
+
+    fn event(value: u64) -> PatchSignals {
+        PatchSignals::new(format!("{{value: {value}}}"))
+    }
+
+    fn ids(events: &[Arc<DatastarEvent>]) -> Vec<String> {
+        events
+            .iter()
+            .map(|event| event.id.clone().unwrap_or_default())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn slow_subscribers_are_disconnected() {
+        let hub = Hub::new(1, 0);
+        let mut slow = hub.subscribe("topic", None);
+        let mut fast = hub.subscribe("topic", None);
+
+        assert_eq!(hub.publish("topic", event(1)), 2);
+        assert!(fast.recv().await.is_some());
+        assert_eq!(hub.publish("topic", event(2)), 1);
+
+        // The slow one still gets what fit in its buffer, then the end
+        assert!(slow.recv().await.is_some());
+        assert!(slow.recv().await.is_none());
+        assert!(fast.recv().await.is_some());
+
+        let metrics = &hub.metrics()[0];
+        assert_eq!(metrics.subscribers, 1);
+        assert_eq!(metrics.slow_consumers, 1);
+        assert_eq!(metrics.delivered, 3);
+    }
+
+    #[tokio::test]
+    async fn replays_missed_events_from_this_epoch_only() {
+        let hub = Hub::new(4, 8);
+        for value in 1..=3 {
+            hub.publish("topic", event(value));
+        }
+
+        let resumed = hub.subscribe("topic", Some(LastEventId(format!("{}-1", hub.epoch))));
+        assert_eq!(
+            ids(&resumed.replay),
+            [format!("{}-2", hub.epoch), format!("{}-3", hub.epoch)]
+        );
+        let earlier_process = format!("{}-1", hub.epoch - 1);
+        assert!(hub.subscribe("topic", Some(LastEventId(earlier_process))).replay.is_empty());
+        let garbage = LastEventId("not-an-id".to_string());
+        assert!(hub.subscribe("topic", Some(garbage)).replay.is_empty());
+        assert_eq!(hub.metrics()[0].replayed, 2);
+    }
+
+    #[tokio::test]
+    async fn history_is_bounded() {
+        let hub = Hub::new(4, 2);
+        for value in 1..=5 {
+            hub.publish("topic", event(value));
+        }
+        let topics = hub.topics.lock().unwrap();
+        assert_eq!(topics["topic"].history.len(), 2);
+        drop(topics);
+
+        // Event 3 is gone, so resuming after 2 would skip it
+        let after_2 = LastEventId(format!("{}-2", hub.epoch));
+        assert!(hub.subscribe("topic", Some(after_2)).replay.is_empty());
+        let after_3 = LastEventId(format!("{}-3", hub.epoch));
+        assert_eq!(
+            ids(&hub.subscribe("topic", Some(after_3)).replay),
+            [format!("{}-4", hub.epoch), format!("{}-5", hub.epoch)]
+        );
+    }
+}
@@ -0,0 +1,11 @@
+pub mod hub;
+pub mod patch;
+pub mod request;
+pub mod signals;
+pub mod stream;
+
+pub use hub::{Hub, Subscription};
+pub use patch::{Patches, TemplatePatch};
+pub use request::fragment_only;
+pub use signals::{SignalPatch, Signals};
+pub use stream::LastEventId;
@@ -0,0 +1,129 @@
+use crate::hypermedia::stream::event_stream;
+use askama::Template;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use datastar::{
+    consts::ElementPatchMode,
+    prelude::{DatastarEvent, PatchElements, PatchSignals},
+};
+
+/// An askama template sent as a Datastar `patch-elements` event.
+///
+/// Without a selector Datastar morphs the rendered elements into the page by
+/// id, so a fragment whose root carries the target id needs no options:
+///
+/// ```ignore
+/// TemplatePatch::new(ExampleFacetsTemplate { facets })
+/// TemplatePatch::new(cards).selector("#example-cards").mode(ElementPatchMode::Inner)
+/// ```
+pub struct TemplatePatch<T> {
+    template: T,
+    selector: Option<String>,
+    mode: ElementPatchMode,
+    view_transition: bool,
+    signals: Option<PatchSignals>,
+}
+
+impl<T: Template> TemplatePatch<T> {
+    pub fn new(template: T) -> Self {
+        Self {
+            template,
+            selector: None,
+            mode: ElementPatchMode::Outer,
+            view_transition: false,
+            signals: None,
+        }
+    }
+
+    /// CSS selector of the element to patch, instead of matching by id.
+    pub fn selector(mut self, selector: impl Into<String>) -> Self {
+        self.selector = Some(selector.into());
+        self
+    }
+
+    pub fn mode(mut self, mode: ElementPatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Applies the patch inside a view transition where the browser supports it.
+    pub fn view_transition(mut self) -> Self {
+        self.view_transition = true;
+        self
+    }
+
+    /// Sends a `patch-signals` event after the elements, in the same stream.
+    pub fn signals(mut self, signals: impl Into<PatchSignals>) -> Self {
+        self.signals = Some(signals.into());
+        self
+    }
+
+    fn render(self) -> Result<(PatchElements, Option<PatchSignals>), askama::Error> {
+        let mut patch = PatchElements::new(self.template.render()?)
+            .mode(self.mode)
+            .use_view_transition(self.view_transition);
+        if let Some(selector) = self.selector {
+            patch = patch.selector(selector);
+        }
+        Ok((patch, self.signals))
+    }
+}
+
+impl<T: Template> IntoResponse for TemplatePatch<T> {
+    fn into_response(self) -> Response {
+        Patches::new().elements(self).into_response()
+    }
+}
+
+/// Several Datastar events answered as one SSE stream, in order.
+///
+/// Templates are rendered as they are added; the first rendering error turns
+/// the whole response into a 500, like a failed page render.
+#[derive(Default)]
+pub struct Patches {
+    events: Vec<DatastarEvent>,
+    error: Option<askama::Error>,
+}
+
+impl Patches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn elements<T: Template>(mut self, patch: TemplatePatch<T>) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        match patch.render() {
+            Ok((elements, signals)) => {
+                self.events.push(elements.into_datastar_event());
+                self.events.extend(signals.map(PatchSignals::into_datastar_event));
+            }
+            Err(err) => self.error = Some(err),
+        }
+        self
+    }
+
+    pub fn signals(mut self, signals: impl Into<PatchSignals>) -> Self {
+        self.events.push(signals.into().into_datastar_event());
+        self
+    }
+}
+
+impl IntoResponse for Patches {
+    fn into_response(self) -> Response {
+        if let Some(err) = self.error {
+            tracing::error!("Template rendering error: {}", err);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+
+        let events = self
+            .events
+            .iter()
+            .map(DatastarEvent::write_as_axum_sse_event)
+            .collect();
+        event_stream(events, None)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    error::AppError,
+    settings::{NonDatastarFallback, Settings},
+};
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts, RawPathParams, Request, State},
+    http::{request::Parts, HeaderMap},
+    middleware::{from_fn_with_state, Next},
+    response::{IntoResponse, Redirect, Response},
+    routing::Route,
+};
+use std::convert::Infallible;
+use tower::{Layer, Service};
+
+/// Header Datastar adds to every backend action.
+pub const DATASTAR_REQUEST_HEADER: &str = "datastar-request";
+
+/// Proof that a request was sent by a Datastar action.
+///
+/// Handlers that only make sense as fragments are better guarded with
+/// [`fragment_only`]; take `Option<DatastarRequest>` where one handler serves
+/// both full pages and fragments.
+#[derive(Debug, Clone, Copy)]
+pub struct DatastarRequest;
+
+impl DatastarRequest {
+    pub fn is_present(headers: &HeaderMap) -> bool {
+        headers.contains_key(DATASTAR_REQUEST_HEADER)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for DatastarRequest {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if Self::is_present(&parts.headers) {
+            Ok(DatastarRequest)
+        } else {
+            Err(AppError::not_acceptable(
+                "This endpoint only serves Datastar responses",
+            ))
+        }
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for DatastarRequest {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(Self::is_present(&parts.headers).then_some(DatastarRequest))
+    }
+}
+
+/// Route layer marking a route as fragment-only.
+///
+/// `owner` is the page the fragments belong to and may use the route's path
+/// parameters, e.g. `/examples/{example_id}`. Requests without the Datastar
+/// header get the response chosen by `hypermedia.fallback`: a redirect to the
+/// owner, or `406 Not Acceptable`.
+pub fn fragment_only(
+    owner: &'static str,
+) -> impl Layer<Route, Service = impl FragmentService> + Clone + Send + Sync + 'static {
+    fragment_only_with(owner, Settings::get().hypermedia.fallback)
+}
+
+/// [`fragment_only`] with the fallback given rather than read from settings.
+fn fragment_only_with(
+    owner: &'static str,
+    fallback: NonDatastarFallback,
+) -> impl Layer<Route, Service = impl FragmentService> + Clone + Send + Sync + 'static {
+    from_fn_with_state((owner, fallback), require_datastar)
+}
+
+/// The service `fragment_only` wraps a route in, spelled out for `route_layer`.
+pub trait FragmentService:
+    Service<Request, Response = Response, Error = Infallible, Future: Send + 'static>
+    + Clone
+    + Send
+    + Sync
+    + 'static
+{
+}
+
+impl<T> FragmentService for T where
+    T: Service<Request, Response = Response, Error = Infallible, Future: Send + 'static>
+        + Clone
+        + Send
+        + Sync
+        + 'static
+{
+}
+
+async fn require_datastar(
+    State((owner, fallback)): State<(&'static str, NonDatastarFallback)>,
+    datastar: Option<DatastarRequest>,
+    params: RawPathParams,
+    request: Request,
+    next: Next,
+) -> Response {
+    if datastar.is_some() {
+        return next.run(request).await;
+    }
+
+    tracing::debug!(path = %request.uri().path(), owner, "Non-Datastar request to a fragment route");
+    match fallback {
+        NonDatastarFallback::Redirect => {
+            let location = params.iter().fold(owner.to_string(), |url, (name, value)| {
+                url.replace(&format!("{{{name}}}"), value)
+            });
+            Redirect::to(&location).into_response()
+        }
+        NonDatastarFallback::NotAcceptable => {
+            AppError::not_acceptable("This endpoint only serves Datastar responses")
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{header, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    async fn send(fallback: NonDatastarFallback, datastar: bool) -> Response {
+        let router = Router::new().route(
+            "/items/{id}/notes/{note}",
+            get(|| async { "fragment" })
+                .route_layer(fragment_only_with("/items/{id}#note-{note}", fallback)),
+        );
+        let mut request = Request::get("/items/42/notes/7");
+        if datastar {
+            request = request.header(DATASTAR_REQUEST_HEADER, "true");
+        }
+        router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn datastar_requests_reach_the_handler() {
+        for fallback in [
+            NonDatastarFallback::Redirect,
+            NonDatastarFallback::NotAcceptable,
+        ] {
+            let response = send(fallback, true).await;
+            assert_eq!(response.status(), StatusCode::OK, "{fallback:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn other_requests_are_redirected_to_the_owner() {
+        let response = send(NonDatastarFallback::Redirect, false).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[header::LOCATION], "/items/42#note-7");
+    }
+
+    #[tokio::test]
+    async fn other_requests_can_be_refused() {
+        let response = send(NonDatastarFallback::NotAcceptable, false).await;
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+        assert!(response.headers().get(header::LOCATION).is_none());
+    }
+}
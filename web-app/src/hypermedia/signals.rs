@@ -0,0 +1,252 @@
+use crate::{error::AppError, hypermedia::Patches};
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Query, Request},
+    http::Method,
+    response::{IntoResponse, Response},
+};
+use datastar::prelude::PatchSignals;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Datastar signals sent with a request, deserialized into `T`.
+///
+/// Datastar puts the signals in the `datastar` query parameter for `GET`
+/// actions and in a JSON body for every other method. A missing
+/// payload is read as `{}`, so `T` decides through `#[serde(default)]` whether
+/// signals are optional. Malformed payloads are rejected with
+/// [`AppError::BadRequest`].
+///
+/// ```ignore
+/// async fn handler(Signals(signals): Signals<SearchSignals>) { ... }
+/// ```
+#[derive(Debug)]
+pub struct Signals<T>(pub T);
+
+#[derive(Deserialize)]
+struct DatastarQuery {
+    datastar: Option<String>,
+}
+
+impl<T, S> FromRequest<S> for Signals<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let payload = if reads_query(req.method()) {
+            let Query(query) = Query::<DatastarQuery>::try_from_uri(req.uri())
+                .map_err(|e| AppError::bad_request(format!("Invalid query string: {}", e)))?;
+            query.datastar.map(Bytes::from).unwrap_or_default()
+        } else {
+            Bytes::from_request(req, state)
+                .await
+                .map_err(|e| AppError::bad_request(format!("Unreadable request body: {}", e)))?
+        };
+
+        let payload: &[u8] = if payload.iter().all(u8::is_ascii_whitespace) {
+            b"{}"
+        } else {
+            &payload
+        };
+
+        serde_json::from_slice(payload)
+            .map(Signals)
+            .map_err(|e| AppError::bad_request(format!("Malformed Datastar signals: {}", e)))
+    }
+}
+
+fn reads_query(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD)
+}
+
+/// A partial signal update, sent as a Datastar `patch-signals` event.
+///
+/// Built from any `Serialize` value that serializes to a JSON object. Nested
+/// objects patch nested signals and leave their siblings alone, so a struct
+/// only needs the fields it changes. Datastar deletes signals patched to
+/// `null`, which is what [`SignalPatch::remove`] sends.
+#[derive(Debug, Clone)]
+pub struct SignalPatch {
+    signals: Map<String, Value>,
+    only_if_missing: bool,
+}
+
+impl SignalPatch {
+    pub fn new(signals: &impl Serialize) -> Result<Self, AppError> {
+        match serde_json::to_value(signals) {
+            Ok(Value::Object(signals)) => Ok(Self {
+                signals,
+                only_if_missing: false,
+            }),
+            Ok(other) => Err(AppError::InternalServerError(anyhow::anyhow!(
+                "Signals must serialize to a JSON object, got {}",
+                other
+            ))),
+            Err(e) => Err(AppError::InternalServerError(anyhow::anyhow!(
+                "Failed to serialize signals: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Only sets signals the page doesn't define yet.
+    pub fn only_if_missing(mut self) -> Self {
+        self.only_if_missing = true;
+        self
+    }
+
+    /// Deletes the signal at a dotted path such as `form.error`.
+    pub fn remove(mut self, path: &str) -> Self {
+        let mut signals = &mut self.signals;
+        let mut segments = path.split('.').peekable();
+        while let Some(segment) = segments.next() {
+            if segments.peek().is_none() {
+                signals.insert(segment.to_string(), Value::Null);
+                break;
+            }
+            let entry = signals
+                .entry(segment)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            let Value::Object(nested) = entry else {
+                unreachable!("entry was just made an object");
+            };
+            signals = nested;
+        }
+        self
+    }
+}
+
+impl From<SignalPatch> for PatchSignals {
+    fn from(patch: SignalPatch) -> Self {
+        PatchSignals::new(Value::Object(patch.signals).to_string())
+            .only_if_missing(patch.only_if_missing)
+    }
+}
+
+impl IntoResponse for SignalPatch {
+    fn into_response(self) -> Response {
+        Patches::new().signals(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use datastar::prelude::DatastarEvent;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        #[serde(default)]
+        query: String,
+        #[serde(default)]
+        page: u32,
+    }
+
+    async fn extract(method: Method, uri: &str, body: &str) -> Result<Search, AppError> {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        Signals::<Search>::from_request(request, &())
+            .await
+            .map(|Signals(signals)| signals)
+    }
+
+    fn search(query: &str, page: u32) -> Search {
+        Search {
+            query: query.to_string(),
+            page,
+        }
+    }
+
+    fn data_lines(patch: SignalPatch) -> Vec<String> {
+        DatastarEvent::from(PatchSignals::from(patch)).data
+    }
+
+    #[test]
+    fn only_if_missing_is_sent_when_set() {
+        let signals = json!({"form": {"first": "name"}});
+        assert_eq!(
+            data_lines(SignalPatch::new(&signals).unwrap().only_if_missing()),
+            ["onlyIfMissing true", r#"signals {"form":{"first":"name"}}"#]
+        );
+        assert_eq!(
+            data_lines(SignalPatch::new(&signals).unwrap()),
+            [r#"signals {"form":{"first":"name"}}"#]
+        );
+    }
+
+    #[test]
+    fn removed_paths_are_patched_to_null() {
+        let patch = SignalPatch::new(&json!({"form": {"status": "saved"}}))
+            .unwrap()
+            .remove("form.error");
+        assert_eq!(
+            data_lines(patch),
+            [r#"signals {"form":{"error":null,"status":"saved"}}"#]
+        );
+    }
+
+    #[tokio::test]
+    async fn get_reads_the_datastar_query() {
+        let uri = "/search?datastar=%7B%22query%22%3A%22sse%22%2C%22page%22%3A2%7D";
+        // The body is ignored for GET
+        let signals = extract(Method::GET, uri, r#"{"query":"body"}"#).await;
+        assert_eq!(signals.unwrap(), search("sse", 2));
+    }
+
+    #[tokio::test]
+    async fn other_methods_read_the_body() {
+        for method in [Method::POST, Method::PUT, Method::PATCH, Method::DELETE] {
+            let signals = extract(
+                method.clone(),
+                "/search?datastar=%7B%7D",
+                r#"{"query":"sse","page":2}"#,
+            )
+            .await;
+            assert_eq!(signals.unwrap(), search("sse", 2), "{method}");
+        }
+    }
+
+    #[tokio::test]
+    async fn empty_payloads_are_an_empty_object() {
+        assert_eq!(
+            extract(Method::GET, "/search", "").await.unwrap(),
+            search("", 0)
+        );
+        assert_eq!(
+            extract(Method::GET, "/search?datastar=", "").await.unwrap(),
+            search("", 0)
+        );
+        assert_eq!(
+            extract(Method::POST, "/search", " \n").await.unwrap(),
+            search("", 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_signals_are_bad_requests() {
+        for (method, uri, body) in [
+            (Method::POST, "/search", r#"{"query":"#),
+            (Method::POST, "/search", r#"{"page":"two"}"#),
+            (Method::POST, "/search", r#""sse""#),
+            (Method::GET, "/search?datastar=%7B", ""),
+            (Method::GET, "/search?datastar=%7B%22page%22%3A-1%7D", ""),
+        ] {
+            let error = extract(method, uri, body).await.unwrap_err();
+            assert!(
+                matches!(error, AppError::BadRequest(_)),
+                "{uri} {body}: {error:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{hypermedia::Subscription, settings::Settings};
+use async_stream::stream;
+use axum::{
+    extract::OptionalFromRequestParts,
+    http::request::Parts,
+    response::{
+        sse::{Event, KeepAlive},
+        IntoResponse, Response, Sse,
+    },
+};
+use std::{convert::Infallible, time::Duration};
+
+/// The `Last-Event-ID` a reconnecting client sends with the id of the last
+/// event it saw, so a topic subscription can replay what it missed.
+#[derive(Debug, Clone)]
+pub struct LastEventId(pub String);
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for LastEventId {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts
+            .headers
+            .get("last-event-id")
+            .and_then(|value| value.to_str().ok())
+            .map(|id| LastEventId(id.trim().to_string()))
+            .filter(|id| !id.0.is_empty()))
+    }
+}
+
+/// Every Datastar SSE response goes through here: `events` are sent first,
+/// then whatever `live` receives until the client disconnects.
+///
+/// The first event carries the configured `retry` hint and idle streams get
+/// a keep-alive comment every `sse.keepalive` seconds, so proxies don't cut
+/// long-lived connections.
+pub(crate) fn event_stream(events: Vec<Event>, live: Option<Subscription>) -> Response {
+    let settings = &Settings::get().sse;
+    let mut retry = Some(Duration::from_millis(settings.retry));
+    let mut with_retry = move |event: Event| match retry.take() {
+        Some(retry) => event.retry(retry),
+        None => event,
+    };
+
+    Sse::new(stream! {
+        for event in events {
+            yield Ok::<Event, Infallible>(with_retry(event));
+        }
+        if let Some(mut live) = live {
+            while let Some(event) = live.recv().await {
+                yield Ok::<Event, Infallible>(with_retry(event.write_as_axum_sse_event()));
+            }
+        }
+    })
+    .keep_alive(KeepAlive::new().interval(Duration::from_secs(settings.keepalive)))
+    .into_response()
+}
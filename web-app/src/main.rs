@@ -1,28 +1,63 @@
-use axum::{
-    response::Json,
-    routing::{get, post},
-    Router,
-};
+use axum::{extract::State, middleware, response::Json, routing::get, Router};
 use serde::Serialize;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
 use tower_http::{services::ServeDir, trace::TraceLayer};
 
+mod accessibility;
+mod catalog;
+mod client_hints;
+mod contrast;
+mod docs;
 mod error;
 mod examples;
 mod examples_gen;
+mod extract;
+// The build script's example header parser, compiled here for its tests
+#[cfg(test)]
+#[path = "../build/header.rs"]
+mod example_header;
 mod handlers;
+mod hypermedia;
+mod preferences;
+mod request_id;
+mod search;
 mod settings;
 mod syntax_highlight;
 mod telemetry;
 mod templates;
+#[cfg(test)]
+mod testing;
 mod theme;
+mod viewport;
 
+use catalog::ExampleCatalog;
+use docs::DocsLibrary;
+use hypermedia::{fragment_only, Hub};
+use preferences::PreferenceCookies;
+use search::SearchIndex;
 use settings::Settings;
+use theme::{generator::GeneratedThemes, ThemeRegistry};
 
 #[derive(Clone)]
 pub struct AppState {
     pub settings: Arc<Settings>,
+    pub examples: Arc<ExampleCatalog>,
+    pub docs: Arc<DocsLibrary>,
+    pub search: Arc<SearchIndex>,
+    pub hub: Arc<Hub>,
+    /// The live counter example's value. Held while an increment is
+    /// published on the hub, so events go out in counter order.
+    pub counter: Arc<Mutex<i64>>,
+    pub themes: Arc<ThemeRegistry>,
+    /// Custom themes generated from visitors' saved seeds.
+    pub custom_themes: Arc<GeneratedThemes>,
+    pub cookies: PreferenceCookies,
+    /// 404s served since startup.
+    pub not_found: Arc<AtomicU64>,
 }
 
 #[derive(Serialize)]
@@ -30,13 +65,15 @@ struct HealthResponse {
     status: String,
     service: String,
     version: String,
+    not_found: u64,
 }
 
-async fn health() -> Json<HealthResponse> {
+async fn health(State(state): State<AppState>) -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "healthy".to_string(),
         service: env!("CARGO_PKG_NAME").to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
+        not_found: state.not_found.load(Ordering::Relaxed),
     })
 }
 
@@ -66,34 +103,95 @@ async fn shutdown_signal() {
     }
 }
 
+impl AppState {
+    /// Loads the example catalog, docs, search index and themes.
+    fn load(settings: &Settings) -> anyhow::Result<Self> {
+        let examples = Arc::new(ExampleCatalog::load());
+        let docs = Arc::new(DocsLibrary::load());
+        let search = Arc::new(SearchIndex::build(&examples, &docs));
+        let themes = ThemeRegistry::load(
+            &Settings::config_dir().join("themes"),
+            &settings.themes,
+        )?;
+        Ok(Self {
+            settings: Arc::new(settings.clone()),
+            examples,
+            docs,
+            search,
+            hub: Arc::new(Hub::new(settings.hub.buffer, settings.hub.history)),
+            counter: Arc::new(Mutex::new(0)),
+            themes: Arc::new(themes),
+            custom_themes: Arc::new(GeneratedThemes::default()),
+            cookies: PreferenceCookies::new(settings)?,
+            not_found: Arc::new(AtomicU64::new(0)),
+        })
+    }
+}
+
+/// Every route and middleware. Example endpoints come from their `@route`
+/// headers.
+fn router(state: AppState) -> Router {
+    examples::register_examples(Router::new())
+        .route("/", get(handlers::index))
+        .route("/examples", get(handlers::examples))
+        .route("/examples/{example_id}", get(handlers::example_page))
+        .route("/docs", get(handlers::docs_index))
+        .route("/docs/{section}/{page}", get(handlers::docs_page))
+        .route("/theme/{file}", get(handlers::theme_stylesheet))
+        .route(
+            "/viewport",
+            get(handlers::viewport_density).route_layer(fragment_only("/")),
+        )
+        .route("/health", get(health))
+        .route("/health/hub", get(handlers::hub_metrics))
+        .route(
+            "/examples/code/{example_id}",
+            get(handlers::get_example_code).route_layer(fragment_only("/examples/{example_id}")),
+        )
+        .method_not_allowed_fallback(handlers::method_not_allowed)
+        .fallback(handlers::not_found)
+        .nest_service("/static", ServeDir::new("static"))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            error::negotiate_errors,
+        ))
+        .layer(middleware::from_fn(client_hints::advertise))
+        .layer(middleware::from_fn(request_id::assign_request_id))
+        .layer(TraceLayer::new_for_http())
+        .with_state(state)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize configuration
     let settings = Settings::init()?;
     tracing::info!("Configuration loaded");
 
+    // `web-app check-contrast` verifies the themes and exits
+    if std::env::args().nth(1).as_deref() == Some("check-contrast") {
+        return Ok(contrast::run(settings)?);
+    }
+
     // Initialize telemetry
     telemetry::init_telemetry(settings)?;
     tracing::info!("Telemetry initialized");
 
     // Create application state
-    let app_state = AppState {
-        settings: Arc::new(settings.clone()),
-    };
-
-    // Build router
-    let app = Router::new()
-        .route("/", get(handlers::index))
-        .route("/examples", get(handlers::examples))
-        .route("/health", get(health))
-        .route("/examples/elements/get-items", get(handlers::get_items))
-        .route("/examples/elements/submit-form", post(handlers::submit_form))
-        .route("/examples/search", get(handlers::search))
-        .route("/examples/theme/switch", get(handlers::switch_theme))
-        .route("/examples/code/{example_id}", get(handlers::get_example_code))
-        .nest_service("/static", ServeDir::new("static"))
-        .layer(TraceLayer::new_for_http())
-        .with_state(app_state);
+    let app_state = AppState::load(settings)?;
+    tracing::info!(
+        examples = app_state.examples.all().len(),
+        docs = app_state.docs.pages().len(),
+        indexed_documents = app_state.search.document_count(),
+        indexed_terms = app_state.search.term_count(),
+        "Example catalog and search index loaded"
+    );
+    tracing::info!(
+        themes = app_state.themes.all().len(),
+        default = %settings.themes.default,
+        "Themes loaded"
+    );
+
+    let app = router(app_state);
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], settings.server.port));
@@ -0,0 +1,148 @@
+use crate::settings::Settings;
+use anyhow::{bail, Context};
+use axum::http::HeaderMap;
+use axum_extra::extract::cookie::{Cookie, Key, SameSite, SignedCookieJar};
+use std::time::Duration;
+
+/// How long preference cookies are kept.
+const MAX_AGE: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// The visitor's theme preference, a theme name, `auto` or `custom`.
+pub const THEME_COOKIE: &str = "theme";
+
+/// Seed of the visitor's generated theme, see `theme::generator::Seed`.
+pub const CUSTOM_THEME_COOKIE: &str = "custom-theme";
+
+/// Reduced motion, high contrast and text size, see
+/// `accessibility::AccessibilityPreferences::encode`.
+pub const ACCESSIBILITY_COOKIE: &str = "accessibility";
+
+/// Signing key for development only, so preferences survive restarts
+/// without configuring a secret. It is public, so never use it elsewhere.
+const DEVELOPMENT_SECRET: &[u8] =
+    b"development-only preference cookie key; set cookies.secret anywhere else";
+
+/// Reads and writes the signed cookies holding visitor preferences.
+///
+/// Values are signed with `cookies.secret`, so a cookie edited by hand or set
+/// by another site reads as absent. Cookies are `HttpOnly`, as pages get
+/// their preferences from the server, and `Secure` outside development.
+#[derive(Clone)]
+pub struct PreferenceCookies {
+    key: Key,
+    secure: bool,
+}
+
+impl PreferenceCookies {
+    pub fn new(settings: &Settings) -> anyhow::Result<Self> {
+        Self::for_environment(
+            &settings.application.environment,
+            settings.cookies.secret.as_deref(),
+        )
+    }
+
+    fn for_environment(environment: &str, secret: Option<&str>) -> anyhow::Result<Self> {
+        let development = environment == "development";
+        let key = match secret {
+            Some(secret) => Key::try_from(secret.as_bytes())
+                .context("`cookies.secret` must be at least 64 bytes")?,
+            None if development => {
+                tracing::warn!("`cookies.secret` is not set, signing cookies with the development key");
+                Key::from(DEVELOPMENT_SECRET)
+            }
+            None => bail!("`cookies.secret` must be set in the `{}` environment", environment),
+        };
+        Ok(Self {
+            key,
+            secure: !development,
+        })
+    }
+
+    /// The value of preference cookie `name`, if it is present and its
+    /// signature checks out.
+    pub fn get(&self, headers: &HeaderMap, name: &str) -> Option<String> {
+        SignedCookieJar::from_headers(headers, self.key.clone())
+            .get(name)
+            .map(|cookie| cookie.value().to_string())
+    }
+
+    /// A jar that sets preference cookie `name` to `value` for a year.
+    /// Callers validate `value`, the jar only signs and encodes it.
+    pub fn set(&self, name: &'static str, value: impl Into<String>) -> SignedCookieJar {
+        let cookie = Cookie::build((name, value.into()))
+            .path("/")
+            .max_age(MAX_AGE.try_into().expect("a year fits a cookie Max-Age"))
+            .same_site(SameSite::Lax)
+            .http_only(true)
+            .secure(self.secure);
+        SignedCookieJar::new(self.key.clone()).add(cookie)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::header::{COOKIE, SET_COOKIE},
+        response::IntoResponse,
+    };
+
+    const SECRET: &str = "a test secret that is long enough to sign cookies with, 64+ bytes";
+
+    /// The `Cookie` request header a browser would send back for `jar`.
+    fn sent_back(jar: SignedCookieJar) -> (HeaderMap, String) {
+        let response = jar.into_response();
+        let set_cookie = response.headers()[SET_COOKIE].to_str().unwrap().to_string();
+        let pair = set_cookie.split(';').next().unwrap().to_string();
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, pair.parse().unwrap());
+        (headers, set_cookie)
+    }
+
+    fn cookie_header(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn signed_cookies_round_trip() {
+        let cookies = PreferenceCookies::for_environment("production", Some(SECRET)).unwrap();
+        let (headers, set_cookie) = sent_back(cookies.set(THEME_COOKIE, "dark"));
+        assert_eq!(cookies.get(&headers, THEME_COOKIE).as_deref(), Some("dark"));
+        for attribute in ["HttpOnly", "SameSite=Lax", "Secure", "Path=/", "Max-Age=31536000"] {
+            assert!(set_cookie.contains(attribute), "{set_cookie}");
+        }
+    }
+
+    #[test]
+    fn tampered_and_unsigned_cookies_are_ignored() {
+        let cookies = PreferenceCookies::for_environment("production", Some(SECRET)).unwrap();
+        let (headers, _) = sent_back(cookies.set(THEME_COOKIE, "dark"));
+        let signed = headers[COOKIE].to_str().unwrap();
+
+        let tampered = cookie_header(&signed.replace("dark", "dim"));
+        assert_eq!(cookies.get(&tampered, THEME_COOKIE), None);
+        assert_eq!(cookies.get(&cookie_header("theme=dark"), THEME_COOKIE), None);
+
+        let other_key = PreferenceCookies::for_environment("development", None).unwrap();
+        assert_eq!(other_key.get(&headers, THEME_COOKIE), None);
+    }
+
+    #[test]
+    fn a_secret_is_required_outside_development() {
+        let error = PreferenceCookies::for_environment("production", None).err().unwrap();
+        assert!(error.to_string().contains("`production`"), "{error}");
+        assert!(PreferenceCookies::for_environment("production", Some("short")).is_err());
+    }
+
+    #[test]
+    fn development_falls_back_to_an_insecure_key() {
+        let cookies = PreferenceCookies::for_environment("development", None).unwrap();
+        let (headers, set_cookie) = sent_back(cookies.set(THEME_COOKIE, "dark"));
+        assert!(!set_cookie.contains("Secure"), "{set_cookie}");
+        // Signed with the same key after a restart
+        let restarted = PreferenceCookies::for_environment("development", None).unwrap();
+        assert_eq!(restarted.get(&headers, THEME_COOKIE).as_deref(), Some("dark"));
+    }
+}
@@ -0,0 +1,622 @@
+use crate::{
+    catalog::ExampleCatalog,
+    docs::{strip_line_numbers, DocsLibrary},
+    examples_gen::get_example_source,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+
+// Field weights: a term in a title counts three times as much as one in the body.
+const TITLE_WEIGHT: f32 = 3.0;
+const KEYWORD_WEIGHT: f32 = 2.0;
+const BODY_WEIGHT: f32 = 1.0;
+
+// How much an expanded query term counts compared to an exact match.
+const PREFIX_MATCH: f32 = 0.7;
+const TYPO_MATCH: f32 = 0.5;
+
+// BM25 parameters.
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+const SNIPPET_CHARS: usize = 180;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    Example,
+    Doc,
+}
+
+impl SearchKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchKind::Example => "Example",
+            SearchKind::Doc => "Docs",
+        }
+    }
+
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            SearchKind::Example => "example",
+            SearchKind::Doc => "doc",
+        }
+    }
+}
+
+/// A ranked search result. `title_html` and `snippet_html` are escaped, with
+/// matched terms wrapped in `<mark>`.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    /// Example id, or `section/slug` for docs.
+    pub key: String,
+    pub url: String,
+    pub title_html: String,
+    pub snippet_html: String,
+    pub score: f32,
+}
+
+struct Document {
+    kind: SearchKind,
+    key: String,
+    url: String,
+    title: String,
+    /// Plain text the snippet is cut from.
+    text: String,
+    /// Weighted number of tokens, for BM25 length normalisation.
+    length: f32,
+}
+
+/// In-process inverted index over example metadata, example source and the
+/// bundled Datastar docs, built once at startup.
+pub struct SearchIndex {
+    documents: Vec<Document>,
+    /// term -> (document index, weighted term frequency), sorted so the
+    /// terms a query term is a prefix of are one range.
+    postings: BTreeMap<String, Vec<(usize, f32)>>,
+    /// Terms by length in chars, the candidates for typo matches.
+    terms_by_length: HashMap<usize, Vec<String>>,
+    average_length: f32,
+}
+
+impl SearchIndex {
+    pub fn build(examples: &ExampleCatalog, docs: &DocsLibrary) -> Self {
+        let mut index = Self::empty();
+
+        for example in examples.all() {
+            let source = get_example_source(&example.id).map_or("", |source| source.code);
+            let keywords = format!(
+                "{} {} {}",
+                example.id,
+                example.category,
+                example.tags.join(" ")
+            );
+            index.add(
+                Document {
+                    kind: SearchKind::Example,
+                    key: example.id.clone(),
+                    url: format!("/examples/{}", example.id),
+                    title: example.title.clone(),
+                    text: format!("{}\n{}", example.description, source),
+                    length: 0.0,
+                },
+                &[
+                    (example.title.as_str(), TITLE_WEIGHT),
+                    (keywords.as_str(), KEYWORD_WEIGHT),
+                    (example.description.as_str(), KEYWORD_WEIGHT),
+                    (example.html.as_str(), BODY_WEIGHT),
+                    (source, BODY_WEIGHT),
+                ],
+            );
+        }
+
+        for page in docs.pages().iter().filter(|page| !page.is_index()) {
+            let text = markdown_to_text(page.body);
+            index.add(
+                Document {
+                    kind: SearchKind::Doc,
+                    key: format!("{}/{}", page.section, page.slug),
+                    url: page.url(),
+                    title: page.title.clone(),
+                    text: text.clone(),
+                    length: 0.0,
+                },
+                &[
+                    (page.title.as_str(), TITLE_WEIGHT),
+                    (page.section, KEYWORD_WEIGHT),
+                    (text.as_str(), BODY_WEIGHT),
+                ],
+            );
+        }
+
+        index.finish();
+        index
+    }
+
+    fn empty() -> Self {
+        Self {
+            documents: Vec::new(),
+            postings: BTreeMap::new(),
+            terms_by_length: HashMap::new(),
+            average_length: 0.0,
+        }
+    }
+
+    /// Computes what depends on every document once all are added.
+    fn finish(&mut self) {
+        let total: f32 = self.documents.iter().map(|doc| doc.length).sum();
+        self.average_length = total / self.documents.len().max(1) as f32;
+        self.terms_by_length.clear();
+        for term in self.postings.keys() {
+            self.terms_by_length
+                .entry(term.chars().count())
+                .or_default()
+                .push(term.clone());
+        }
+    }
+
+    fn add(&mut self, mut document: Document, fields: &[(&str, f32)]) {
+        let doc_id = self.documents.len();
+        let mut frequencies: HashMap<String, f32> = HashMap::new();
+        for (text, weight) in fields {
+            for token in tokenize(text) {
+                *frequencies.entry(token).or_default() += weight;
+                document.length += weight;
+            }
+        }
+        for (term, frequency) in frequencies {
+            self.postings.entry(term).or_default().push((doc_id, frequency));
+        }
+        self.documents.push(document);
+    }
+
+    pub fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.postings.len()
+    }
+
+    /// The page closest to a mistyped or stale URL, preferring pages under the
+    /// same first segment (`/examples/...` or `/docs/...`).
+    ///
+    /// A slug within a few edits of the last segment wins, so
+    /// `/examples/actve-search` finds Active Search; otherwise the words of
+    /// each segment, last first, are searched.
+    pub fn suggest(&self, path: &str) -> Option<SearchHit> {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let area = format!("/{}/", segments.first().copied().unwrap_or_default());
+        let other_area = |url: &str| !url.starts_with(&area);
+
+        if let Some(last) = segments.last() {
+            let wanted = normalize_slug(last);
+            let max_edits = (wanted.chars().count() / 3).max(2);
+            let nearest = self
+                .documents
+                .iter()
+                .map(|doc| {
+                    let slug = doc.key.rsplit('/').next().unwrap_or_default();
+                    (edit_distance(&wanted, &normalize_slug(slug)), other_area(&doc.url), doc)
+                })
+                .filter(|(edits, _, _)| *edits <= max_edits)
+                .min_by_key(|(edits, other, _)| (*edits, *other));
+            if let Some((_, _, doc)) = nearest {
+                return Some(SearchHit {
+                    kind: doc.kind,
+                    key: doc.key.clone(),
+                    url: doc.url.clone(),
+                    title_html: escape_html(&doc.title),
+                    snippet_html: String::new(),
+                    score: 0.0,
+                });
+            }
+        }
+
+        segments.iter().rev().find_map(|segment| {
+            let mut hits = self.search(&segment.replace(['-', '_', '.'], " "));
+            let best = hits.iter().position(|hit| !other_area(&hit.url)).unwrap_or(0);
+            (!hits.is_empty()).then(|| hits.swap_remove(best))
+        })
+    }
+
+    /// Ranks every document matching all query terms. Each query term also
+    /// matches index terms it is a prefix of, and terms within a small edit
+    /// distance, at a reduced weight.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let mut query_terms = tokenize(query);
+        query_terms.sort();
+        query_terms.dedup();
+        if query_terms.is_empty() {
+            return Vec::new();
+        }
+
+        let total_docs = self.documents.len() as f32;
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        let mut matched_terms: HashSet<&str> = HashSet::new();
+
+        for (position, query_term) in query_terms.iter().enumerate() {
+            // Best contribution of this query term per document.
+            let mut best: HashMap<usize, f32> = HashMap::new();
+            for (term, weight) in self.expansions(query_term) {
+                let postings = &self.postings[term];
+                matched_terms.insert(term);
+                let df = postings.len() as f32;
+                let idf = (1.0 + (total_docs - df + 0.5) / (df + 0.5)).ln();
+                for &(doc_id, tf) in postings {
+                    let length = self.documents[doc_id].length / self.average_length;
+                    let bm25 = idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length));
+                    let entry = best.entry(doc_id).or_default();
+                    *entry = entry.max(weight * bm25);
+                }
+            }
+
+            // Documents have to match every query term.
+            if position == 0 {
+                scores = best;
+            } else {
+                scores.retain(|doc_id, _| best.contains_key(doc_id));
+                for (doc_id, score) in scores.iter_mut() {
+                    *score += best[doc_id];
+                }
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .map(|(doc_id, score)| {
+                let doc = &self.documents[doc_id];
+                SearchHit {
+                    kind: doc.kind,
+                    key: doc.key.clone(),
+                    url: doc.url.clone(),
+                    title_html: highlight(&doc.title, &matched_terms),
+                    snippet_html: snippet(&doc.text, &matched_terms),
+                    score,
+                }
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.key.cmp(&b.key)));
+        hits
+    }
+
+    /// Index terms a query term matches, with their weight: the term itself,
+    /// terms it is a prefix of, and terms of a similar length within a few
+    /// edits. Only the typo candidates are compared char by char.
+    fn expansions<'a>(&'a self, query_term: &str) -> Vec<(&'a str, f32)> {
+        let mut expansions = Vec::new();
+        if let Some((term, _)) = self.postings.get_key_value(query_term) {
+            expansions.push((term.as_str(), 1.0));
+        }
+        expansions.extend(
+            self.postings
+                .range::<str, _>((Bound::Excluded(query_term), Bound::Unbounded))
+                .map(|(term, _)| term.as_str())
+                .take_while(|term| term.starts_with(query_term))
+                .map(|term| (term, PREFIX_MATCH)),
+        );
+
+        let length = query_term.chars().count();
+        let max_typos = match length {
+            0..=4 => return expansions,
+            5..=8 => 1,
+            _ => 2,
+        };
+        for candidate_length in length - max_typos..=length + max_typos {
+            let Some(terms) = self.terms_by_length.get(&candidate_length) else {
+                continue;
+            };
+            expansions.extend(
+                terms
+                    .iter()
+                    .filter(|term| !term.starts_with(query_term))
+                    .filter(|term| edit_distance(query_term, term) <= max_typos)
+                    .map(|term| (term.as_str(), TYPO_MATCH)),
+            );
+        }
+        expansions
+    }
+}
+
+/// Lowercased alphanumeric runs of at least two characters.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.chars().count() >= 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// `Active_Search` and `active-search` compare equal.
+fn normalize_slug(slug: &str) -> String {
+    slug.to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Strips the markdown syntax that would otherwise show up in snippets, and
+/// the line number gutters of crawled code blocks, so `12` in a gutter is not
+/// a search hit.
+fn markdown_to_text(markdown: &str) -> String {
+    let mut text = String::with_capacity(markdown.len());
+    let mut code: Option<String> = None;
+    for line in markdown.lines() {
+        if line.trim().starts_with("```") {
+            match code.take() {
+                Some(block) => push_text(&mut text, &strip_line_numbers(&block)),
+                None => code = Some(String::new()),
+            }
+            continue;
+        }
+        match &mut code {
+            Some(block) => {
+                block.push_str(line);
+                block.push('\n');
+            }
+            None => push_text(&mut text, line),
+        }
+    }
+    if let Some(block) = code {
+        push_text(&mut text, &strip_line_numbers(&block));
+    }
+    text
+}
+
+fn push_text(text: &mut String, markdown: &str) {
+    for line in markdown.lines() {
+        let line = line.trim();
+        if line == "---" {
+            continue;
+        }
+        let line = line.trim_start_matches(['#', '>', '-', '*']).trim();
+        // Heading anchors from the crawler: `Title [#](#title)`
+        let line = match line.find(" [#](") {
+            Some(anchor) => &line[..anchor],
+            None => line,
+        };
+        text.push_str(&strip_links(line).replace(['*', '`'], ""));
+        text.push('\n');
+    }
+}
+
+/// Rewrites `[label](url)` to `label`.
+fn strip_links(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|i| close + i) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        out.push_str(&rest[open + 1..close]);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes `text` and wraps every token that matched the query in `<mark>`.
+fn highlight(text: &str, matched_terms: &HashSet<&str>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut start = 0;
+    for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        if c.is_alphanumeric() {
+            continue;
+        }
+        let token = &text[start..index];
+        if !token.is_empty() && matched_terms.contains(token.to_lowercase().as_str()) {
+            out.push_str("<mark>");
+            out.push_str(&escape_html(token));
+            out.push_str("</mark>");
+        } else {
+            out.push_str(&escape_html(token));
+        }
+        if index < text.len() {
+            out.push_str(&escape_html(&text[index..index + c.len_utf8()]));
+        }
+        start = index + c.len_utf8();
+    }
+    out
+}
+
+/// A short highlighted excerpt around the first matched term.
+fn snippet(text: &str, matched_terms: &HashSet<&str>) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let first_match = text
+        .split(|c: char| !c.is_alphanumeric())
+        .find(|token| matched_terms.contains(token.to_lowercase().as_str()))
+        .map(|token| token.as_ptr() as usize - text.as_ptr() as usize)
+        .unwrap_or(0);
+
+    // Start a little before the match, on a word boundary.
+    let mut start = first_match.saturating_sub(SNIPPET_CHARS / 3);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    if start > 0 {
+        start = text[start..first_match]
+            .find(' ')
+            .map_or(first_match, |space| start + space + 1);
+    }
+    let mut end = (start + SNIPPET_CHARS).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    out.push_str(&highlight(&text[start..end], matched_terms));
+    if end < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An index over `(title, body)` pairs, keyed by position.
+    fn index_of(documents: &[(&str, &str)]) -> SearchIndex {
+        let mut index = SearchIndex::empty();
+        for (position, (title, body)) in documents.iter().enumerate() {
+            index.add(
+                Document {
+                    kind: SearchKind::Example,
+                    key: position.to_string(),
+                    url: format!("/examples/{}", position),
+                    title: title.to_string(),
+                    text: body.to_string(),
+                    length: 0.0,
+                },
+                &[(title, TITLE_WEIGHT), (body, BODY_WEIGHT)],
+            );
+        }
+        index.finish();
+        index
+    }
+
+    fn keys(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.key.as_str()).collect()
+    }
+
+    fn gallery() -> SearchIndex {
+        index_of(&[
+            ("Active Search", "Filter the list as you type"),
+            ("Infinite Scroll", "Load more rows as you scroll down"),
+            (
+                "Click to Edit",
+                "Inline editing of a contact, then scroll back",
+            ),
+        ])
+    }
+
+    #[test]
+    fn tokens_are_lowercased_runs_of_two_or_more() {
+        assert_eq!(
+            tokenize("Hello, Wörld! a b2 _x_ SSE"),
+            ["hello", "wörld", "b2", "sse"]
+        );
+        assert!(tokenize("a - b").is_empty());
+    }
+
+    #[test]
+    fn exact_prefix_and_typo_matches() {
+        let index = gallery();
+        assert_eq!(keys(&index.search("infinite")), ["1"]);
+        assert_eq!(keys(&index.search("INFIN")), ["1"]);
+        assert_eq!(keys(&index.search("infinte")), ["1"]);
+        assert_eq!(keys(&index.search("serch")), ["0"]);
+        assert!(index.search("nothing").is_empty());
+    }
+
+    #[test]
+    fn every_query_term_has_to_match() {
+        let index = gallery();
+        assert_eq!(keys(&index.search("scroll edit")), ["2"]);
+        assert!(index.search("scroll search").is_empty());
+    }
+
+    #[test]
+    fn ranks_titles_and_exact_matches_first() {
+        let index = gallery();
+        // In the title of one and the body of the other
+        assert_eq!(keys(&index.search("scroll")), ["1", "2"]);
+
+        let lengths = index_of(&[("Streams", "typed"), ("Stream", "type")]);
+        assert_eq!(keys(&lengths.search("stream")), ["1", "0"]);
+        assert_eq!(keys(&lengths.search("types")), ["0", "1"]);
+    }
+
+    #[test]
+    fn short_queries_find_nothing_or_only_prefixes() {
+        let index = gallery();
+        assert!(index.search("").is_empty());
+        assert!(index.search("a").is_empty());
+        // Four chars are too few for typos, but still a prefix
+        assert!(index.search("scrl").is_empty());
+        assert_eq!(keys(&index.search("scro")), ["1", "2"]);
+    }
+
+    #[test]
+    fn titles_and_snippets_are_escaped_and_highlighted() {
+        let index = index_of(&[(
+            "<Fetch> & Stream",
+            "Use fetch() with \"quotes\" & <b>tags</b>",
+        )]);
+        let hits = index.search("fetch");
+        assert_eq!(
+            hits[0].title_html,
+            "&lt;<mark>Fetch</mark>&gt; &amp; Stream"
+        );
+        assert_eq!(
+            hits[0].snippet_html,
+            "Use <mark>fetch</mark>() with &quot;quotes&quot; &amp; &lt;b&gt;tags&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn long_snippets_are_cut_around_the_match() {
+        let body = format!("{} needle {}", "hay ".repeat(100), "stack ".repeat(100));
+        let index = index_of(&[("Title", &body)]);
+        let snippet = &index.search("needle")[0].snippet_html;
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("<mark>needle</mark>"));
+        let text = snippet.replace("<mark>", "").replace("</mark>", "");
+        assert!(text.chars().count() <= SNIPPET_CHARS + 2);
+    }
+
+    #[test]
+    fn code_block_gutters_are_not_indexed() {
+        let lines: Vec<String> = (1..=12)
+            .map(|number| format!("{number:>2}<li>item</li>"))
+            .collect();
+        let markdown = format!(
+            "## Lists [#](#lists)\n\n```html\n{}\n```\n\nShows 12 items.\n",
+            lines.join("\n")
+        );
+        let text = markdown_to_text(&markdown);
+        assert!(text.starts_with("Lists\n"), "{text}");
+        assert!(text.contains("\n<li>item</li>\n"), "{text}");
+        assert!(!text.contains("10<li>"), "{text}");
+
+        // Only the prose mentions 12
+        let code_only = markdown_to_text(&markdown.replace("Shows 12 items.", ""));
+        let index = index_of(&[("Lists", &text), ("Code only", &code_only)]);
+        assert_eq!(keys(&index.search("12")), ["0"]);
+        assert_eq!(keys(&index.search("11")), Vec::<&str>::new());
+        assert_eq!(keys(&index.search("item")), ["0", "1"]);
+    }
+}
@@ -1,6 +1,7 @@
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
 use std::env;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -9,6 +10,12 @@ pub struct Settings {
     pub application: ApplicationSettings,
     pub server: ServerSettings,
     pub telemetry: TelemetrySettings,
+    pub hypermedia: HypermediaSettings,
+    pub hub: HubSettings,
+    pub sse: SseSettings,
+    pub themes: ThemeSettings,
+    #[serde(default)]
+    pub cookies: CookieSettings,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -35,6 +42,79 @@ pub struct TelemetrySettings {
     pub otlp_endpoint: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct HypermediaSettings {
+    /// What fragment-only routes answer to requests that aren't Datastar actions.
+    pub fallback: NonDatastarFallback,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NonDatastarFallback {
+    /// `303 See Other` to the page that owns the fragment.
+    Redirect,
+    /// `406 Not Acceptable`.
+    NotAcceptable,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct HubSettings {
+    /// Events a live-stream subscriber may fall behind by before it is dropped.
+    pub buffer: usize,
+    /// Events kept per topic for `Last-Event-ID` replay.
+    pub history: usize,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SseSettings {
+    /// Seconds between keep-alive comments on an idle stream.
+    pub keepalive: u64,
+    /// Milliseconds a disconnected client waits before reconnecting.
+    pub retry: u64,
+}
+
+impl SseSettings {
+    /// Shortest `retry` accepted. Anything less has every client of a
+    /// restarting server reconnecting in a tight loop.
+    pub const MIN_RETRY: u64 = 100;
+
+    /// Rejects intervals that would spin: no keep-alive interval, or a
+    /// `retry` under [`Self::MIN_RETRY`] milliseconds.
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.keepalive == 0 {
+            return Err(ConfigError::Message(
+                "`sse.keepalive` must be at least 1 second".to_string(),
+            ));
+        }
+        if self.retry < Self::MIN_RETRY {
+            return Err(ConfigError::Message(format!(
+                "`sse.retry` must be at least {} milliseconds, got {}",
+                Self::MIN_RETRY,
+                self.retry
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ThemeSettings {
+    /// Theme for visitors without a preference, by file name in
+    /// `config/themes`, or `auto` to follow their OS.
+    pub default: String,
+    /// What `auto` shows when the OS prefers a light color scheme.
+    pub light: String,
+    /// What `auto` shows when the OS prefers a dark color scheme.
+    pub dark: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct CookieSettings {
+    /// Key signing preference cookies, at least 64 bytes. Required outside
+    /// the `development` environment.
+    pub secret: Option<String>,
+}
+
 static SETTINGS: OnceLock<Settings> = OnceLock::new();
 
 impl Settings {
@@ -45,9 +125,8 @@ impl Settings {
 
         let run_environment = env::var("RUN_ENVIRONMENT").unwrap_or_else(|_| "local".into());
 
-        // Use CARGO_MANIFEST_DIR to find config files relative to the package
-        let config_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("config");
-        
+        let config_dir = Self::config_dir();
+
         let s = Config::builder()
             // Set metadata defaults from Cargo.toml
             .set_default("metadata.name", env!("CARGO_PKG_NAME"))?
@@ -61,6 +140,7 @@ impl Settings {
             .build()?;
 
         let settings: Settings = s.try_deserialize()?;
+        settings.sse.validate()?;
 
         SETTINGS
             .set(settings)
@@ -69,8 +149,36 @@ impl Settings {
         Ok(SETTINGS.get().unwrap())
     }
 
+    /// Directory holding `default.toml`, the per-environment files and `themes/`.
+    pub fn config_dir() -> PathBuf {
+        // Use CARGO_MANIFEST_DIR to find config files relative to the package
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("config")
+    }
+
     pub fn get() -> &'static Settings {
         SETTINGS.get().expect("Settings not initialized")
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sse(keepalive: u64, retry: u64) -> SseSettings {
+        SseSettings { keepalive, retry }
+    }
+
+    #[test]
+    fn sse_intervals_are_validated() {
+        assert!(sse(15, 1000).validate().is_ok());
+        assert!(sse(1, SseSettings::MIN_RETRY).validate().is_ok());
+
+        let error = sse(0, 1000).validate().unwrap_err().to_string();
+        assert!(error.contains("sse.keepalive"), "{error}");
+        for retry in [0, SseSettings::MIN_RETRY - 1] {
+            let error = sse(15, retry).validate().unwrap_err().to_string();
+            assert!(error.contains("sse.retry"), "{error}");
+        }
+    }
+}
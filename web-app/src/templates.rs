@@ -1,14 +1,48 @@
 use askama::Template;
 use axum::response::{Html, IntoResponse, Response};
 use chrono::{DateTime, Utc};
-use crate::handlers::SearchResult;
+use std::sync::Arc;
+use crate::accessibility::AccessibilityPreferences;
+use crate::catalog::ExampleFacets;
+use crate::docs::{DocPage, DocSection};
+use crate::examples_gen::ExampleLayout;
+use crate::search::SearchHit;
+use crate::theme::Theme;
+
+/// The theme a page renders with and the preference that chose it.
+#[derive(Debug, Clone)]
+pub struct PageTheme {
+    /// [`Theme::stylesheet_url`] of the rendered theme.
+    pub stylesheet: String,
+    /// A theme name or `auto`, seeds `$theme`.
+    pub preference: String,
+    pub accessibility: PageAccessibility,
+}
+
+/// The visitor's accessibility preferences and the CSS variables applying
+/// them, which come after the theme stylesheet so they can override it.
+#[derive(Debug, Clone)]
+pub struct PageAccessibility {
+    /// As chosen, seeds `$accessibility`.
+    pub preferences: AccessibilityPreferences,
+    /// [`AccessibilityPreferences::stylesheet`] of the preferences resolved
+    /// with the request's client hints.
+    pub stylesheet: String,
+}
+
+impl PageAccessibility {
+    /// `$accessibility` as a JSON object.
+    pub fn signals(&self) -> String {
+        serde_json::to_string(&self.preferences).unwrap_or_else(|_| "{}".to_string())
+    }
+}
 
 #[derive(Template)]
 #[template(path = "index.html")]
 pub struct IndexTemplate {
     pub title: String,
     pub environment: String,
-    pub theme_css: String,
+    pub theme: PageTheme,
 }
 
 #[derive(Template)]
@@ -30,148 +64,185 @@ pub struct ExampleWithHighlight {
     pub id: String,
     pub title: String,
     pub description: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    pub layout: ExampleLayout,
     pub html: String,
     pub highlighted_html: String,
+    pub highlighted_source: String,
     pub backend_file: String,
 }
 
+impl ExampleWithHighlight {
+    /// Whether the live demo is rendered on the card front.
+    pub fn shows_demo_inline(&self) -> bool {
+        self.layout == ExampleLayout::Inline
+    }
+}
+
 #[derive(Template)]
 #[template(path = "examples.html")]
 pub struct ExamplesTemplate {
     pub title: String,
     pub environment: String,
-    pub theme_css: String,
-    pub syntax_css: String,
-    pub examples: Vec<ExampleWithHighlight>,
+    pub theme: PageTheme,
+    /// Pinned examples, always visible above the searchable cards.
+    pub featured: Vec<Arc<ExampleWithHighlight>>,
+    /// Unpinned examples, replaced by search results.
+    pub examples: Vec<Arc<ExampleWithHighlight>>,
+    pub facets: ExampleFacets,
+}
+
+#[derive(Template)]
+#[template(path = "example.html")]
+pub struct ExamplePageTemplate {
+    pub title: String,
+    pub environment: String,
+    pub theme: PageTheme,
+    pub example: Arc<ExampleWithHighlight>,
+}
+
+#[derive(Template)]
+#[template(path = "docs.html")]
+pub struct DocsPageTemplate {
+    pub title: String,
+    pub environment: String,
+    pub theme: PageTheme,
+    pub sections: Vec<DocSection>,
+    pub current_url: String,
+    pub page: DocPage,
+}
+
+#[derive(Template)]
+#[template(path = "not_found.html")]
+pub struct NotFoundTemplate {
+    pub title: String,
+    pub environment: String,
+    pub theme: PageTheme,
+    pub message: String,
+    /// The closest example or docs page to the missing path.
+    pub suggestion: Option<SearchHit>,
+}
+
+/// Themed page for errors on browser navigations.
+#[derive(Template)]
+#[template(path = "error.html")]
+pub struct ErrorPageTemplate {
+    pub title: String,
+    pub environment: String,
+    pub theme: PageTheme,
+    pub status: u16,
+    pub message: String,
+    pub code: &'static str,
+    pub request_id: String,
+}
+
+/// Appended to `#toasts` when a Datastar action fails.
+#[derive(Template)]
+#[template(path = "fragments/error_toast.html")]
+pub struct ErrorToastTemplate {
+    pub message: String,
+    pub code: &'static str,
+    pub request_id: String,
 }
 
 #[derive(Template)]
 #[template(path = "fragments/search_results.html")]
 pub struct SearchResultsTemplate {
-    pub results: Vec<SearchResult>,
+    pub query: String,
+    /// Number of matches before truncating to `hits`.
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
 }
 
 #[derive(Template)]
 #[template(path = "fragments/example_search_results.html")]
 pub struct ExampleSearchResultsTemplate {
-    pub examples: Vec<ExampleWithHighlight>,
+    pub examples: Vec<Arc<ExampleWithHighlight>>,
 }
 
 #[derive(Template)]
-#[template(path = "fragments/backend_code.html")]
-pub struct BackendCodeTemplate {
-    pub example_id: String,
-    pub code: String,
+#[template(path = "fragments/example_facets.html")]
+pub struct ExampleFacetsTemplate {
+    pub facets: ExampleFacets,
 }
 
-impl IntoResponse for IndexTemplate {
-    fn into_response(self) -> Response {
-        match self.render() {
-            Ok(html) => Html(html).into_response(),
-            Err(err) => {
-                tracing::error!("Template rendering error: {}", err);
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal Server Error",
-                )
-                    .into_response()
-            }
-        }
-    }
+/// The page's theme `<link>`, morphed by the theme switcher to load another stylesheet.
+#[derive(Template)]
+#[template(path = "fragments/theme_styles.html")]
+pub struct ThemeStylesTemplate {
+    pub theme: PageTheme,
 }
 
-impl IntoResponse for DataItemsTemplate {
-    fn into_response(self) -> Response {
-        match self.render() {
-            Ok(html) => Html(html).into_response(),
-            Err(err) => {
-                tracing::error!("Template rendering error: {}", err);
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal Server Error",
-                )
-                    .into_response()
-            }
-        }
-    }
+/// The page's accessibility `<style>`, morphed when the preferences change.
+#[derive(Template)]
+#[template(path = "fragments/accessibility_styles.html")]
+pub struct AccessibilityStylesTemplate {
+    pub accessibility: PageAccessibility,
 }
 
-impl IntoResponse for FormResponseTemplate {
-    fn into_response(self) -> Response {
-        match self.render() {
-            Ok(html) => Html(html).into_response(),
-            Err(err) => {
-                tracing::error!("Template rendering error: {}", err);
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal Server Error",
-                )
-                    .into_response()
-            }
-        }
-    }
+/// One radio per registered theme, in display order.
+#[derive(Template)]
+#[template(path = "fragments/theme_options.html")]
+pub struct ThemeOptionsTemplate {
+    pub themes: Vec<Theme>,
 }
 
-impl IntoResponse for ExamplesTemplate {
-    fn into_response(self) -> Response {
-        match self.render() {
-            Ok(html) => Html(html).into_response(),
-            Err(err) => {
-                tracing::error!("Template rendering error: {}", err);
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal Server Error",
-                )
-                    .into_response()
-            }
-        }
-    }
+/// A generated theme applied to the builder's preview only: its variables
+/// are scoped to `#theme-builder-preview`, the page keeps its own theme.
+#[derive(Template)]
+#[template(path = "fragments/theme_builder_preview.html")]
+pub struct ThemeBuilderPreviewTemplate {
+    pub declarations: String,
+    /// Highlighted sample code, drawn with the theme's token colors.
+    pub sample: String,
+    pub checks: usize,
+    pub lowest_contrast: f64,
+    pub passed: bool,
 }
 
-impl IntoResponse for SearchResultsTemplate {
-    fn into_response(self) -> Response {
-        match self.render() {
-            Ok(html) => Html(html).into_response(),
-            Err(err) => {
-                tracing::error!("Template rendering error: {}", err);
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal Server Error",
-                )
-                    .into_response()
-            }
-        }
-    }
+#[derive(Template)]
+#[template(path = "fragments/backend_code.html")]
+pub struct BackendCodeTemplate {
+    pub example_id: String,
+    pub code: String,
 }
 
-impl IntoResponse for BackendCodeTemplate {
-    fn into_response(self) -> Response {
-        match self.render() {
-            Ok(html) => Html(html).into_response(),
-            Err(err) => {
-                tracing::error!("Template rendering error: {}", err);
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal Server Error",
-                )
-                    .into_response()
-            }
+/// Renders a page template to HTML, or a 500 if rendering fails.
+fn render_page(template: &impl Template) -> Response {
+    match template.render() {
+        Ok(html) => Html(html).into_response(),
+        Err(err) => {
+            tracing::error!("Template rendering error: {}", err);
+            (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal Server Error",
+            )
+                .into_response()
         }
     }
 }
 
-impl IntoResponse for ExampleSearchResultsTemplate {
-    fn into_response(self) -> Response {
-        match self.render() {
-            Ok(html) => Html(html).into_response(),
-            Err(err) => {
-                tracing::error!("Template rendering error: {}", err);
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal Server Error",
-                )
-                    .into_response()
+/// Full pages answer as HTML; fragments are sent as Datastar patches through
+/// [`crate::hypermedia::TemplatePatch`] instead.
+macro_rules! page_response {
+    ($($template:ty),+ $(,)?) => {
+        $(
+            impl IntoResponse for $template {
+                fn into_response(self) -> Response {
+                    render_page(&self)
+                }
             }
-        }
-    }
-}
\ No newline at end of file
+        )+
+    };
+}
+
+page_response!(
+    IndexTemplate,
+    ExamplesTemplate,
+    ExamplePageTemplate,
+    DocsPageTemplate,
+    NotFoundTemplate,
+    ErrorPageTemplate,
+);
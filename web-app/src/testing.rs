@@ -0,0 +1,28 @@
+use crate::{router, settings::Settings, AppState};
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+    response::Response,
+};
+use std::sync::LazyLock;
+use tower::ServiceExt;
+
+/// The app's state from `config/`, loaded once for every test that needs it.
+static STATE: LazyLock<AppState> = LazyLock::new(|| {
+    let settings = Settings::init().expect("test settings load");
+    AppState::load(settings).expect("test state loads")
+});
+
+pub fn state() -> AppState {
+    STATE.clone()
+}
+
+/// Sends `request` through the full router, middleware included.
+pub async fn send(request: Request<Body>) -> Response {
+    router(state()).oneshot(request).await.unwrap()
+}
+
+pub async fn body_text(response: Response) -> String {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
@@ -0,0 +1,360 @@
+use super::{
+    BrandColors, ColorScheme, ShadowSettings, SurfaceColors, SyntaxColors, TextColors, Theme,
+    ThemeFile,
+};
+use crate::contrast::{
+    color::{self, Oklch, Rgb},
+    AA_NORMAL_TEXT,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// Name of the theme generated from a visitor's [`Seed`].
+pub const CUSTOM: &str = "custom";
+
+/// Contrast every generated text and token color is tuned to, with headroom
+/// over AA for the rounding of the emitted `oklch()` values.
+const TARGET_CONTRAST: f64 = AA_NORMAL_TEXT + 0.1;
+/// Chroma of the surfaces at full vibrancy; they stay close to neutral.
+const MAX_TINT: f64 = 0.05;
+/// Chroma of the brand and syntax colors at zero and at full vibrancy.
+const ACCENT_CHROMA: (f64, f64) = (0.04, 0.18);
+/// How far each lightness search moves per step.
+const STEP: f64 = 0.01;
+/// Generated themes kept by [`GeneratedThemes`].
+const CACHED_THEMES: usize = 256;
+
+/// Lightness of `--surface-0`'s gradient stops, `--surface-1` to
+/// `--surface-4`, and where the text and accent searches start.
+struct Plan {
+    background: [f64; 2],
+    levels: [f64; 4],
+    primary: f64,
+    secondary: f64,
+    accent: f64,
+    /// Direction that adds contrast: lighter on dark surfaces, darker on
+    /// light ones.
+    step: f64,
+    shadow: f64,
+    shadow_strength: &'static str,
+}
+
+const DARK: Plan = Plan {
+    background: [0.19, 0.25],
+    levels: [0.21, 0.25, 0.29, 0.34],
+    primary: 0.94,
+    secondary: 0.8,
+    accent: 0.76,
+    step: STEP,
+    shadow: 0.12,
+    shadow_strength: "50%",
+};
+
+const LIGHT: Plan = Plan {
+    background: [0.97, 0.91],
+    levels: [0.99, 0.96, 0.93, 0.89],
+    primary: 0.22,
+    secondary: 0.42,
+    accent: 0.5,
+    step: -STEP,
+    shadow: 0.75,
+    shadow_strength: "10%",
+};
+
+/// What a custom theme is generated from. The same seed always generates the
+/// same theme, so the seed is all that is stored or put in a URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Seed {
+    /// OKLCH hue in degrees, `0..360`.
+    pub hue: u16,
+    /// How colorful the theme is, `0..=100`: 0 is gray surfaces with muted
+    /// tokens.
+    pub vibrancy: u8,
+    pub scheme: ColorScheme,
+}
+
+impl Seed {
+    pub fn new(hue: u16, vibrancy: u8, scheme: ColorScheme) -> Self {
+        Self {
+            hue: hue % 360,
+            vibrancy: vibrancy.min(100),
+            scheme,
+        }
+    }
+
+    /// The seed for a brand color: its hue, and a vibrancy matching its
+    /// chroma.
+    pub fn from_color(color: Rgb, scheme: ColorScheme) -> Self {
+        let oklch = Oklch::from_rgb(color);
+        let (min, max) = ACCENT_CHROMA;
+        let vibrancy = ((oklch.c - min) / (max - min) * 100.0).clamp(0.0, 100.0);
+        Self::new(oklch.h.round() as u16, vibrancy.round() as u8, scheme)
+    }
+
+    /// `<hue>-<vibrancy>-<scheme>`, as stored in the cookie and the
+    /// stylesheet URL.
+    pub fn encode(&self) -> String {
+        format!("{}-{}-{}", self.hue, self.vibrancy, self.scheme.as_str())
+    }
+
+    /// Reads [`Seed::encode`] back, rejecting anything out of range.
+    pub fn decode(value: &str) -> Option<Self> {
+        let mut parts = value.split('-');
+        let hue = parts.next()?.parse::<u16>().ok().filter(|hue| *hue < 360)?;
+        let vibrancy = parts.next()?.parse::<u8>().ok().filter(|v| *v <= 100)?;
+        let scheme = match parts.next()? {
+            "light" => ColorScheme::Light,
+            "dark" => ColorScheme::Dark,
+            _ => return None,
+        };
+        parts.next().is_none().then_some(Self::new(hue, vibrancy, scheme))
+    }
+
+    /// The full theme in `oklch()` colors. Text is tuned against every
+    /// surface and tokens against the code surface until they meet AA.
+    pub fn generate(&self) -> Theme {
+        let plan = match self.scheme {
+            ColorScheme::Dark => &DARK,
+            ColorScheme::Light => &LIGHT,
+        };
+        let hue = f64::from(self.hue);
+        let vibrancy = f64::from(self.vibrancy) / 100.0;
+        let tint = MAX_TINT * vibrancy;
+        let accent = ACCENT_CHROMA.0 + (ACCENT_CHROMA.1 - ACCENT_CHROMA.0) * vibrancy;
+
+        let background = plan.background.map(|l| swatch(l, tint, hue));
+        let levels = plan.levels.map(|l| swatch(l, tint, hue));
+        let surfaces: Vec<Rgb> = background
+            .iter()
+            .chain(&levels)
+            .map(|(_, rgb)| *rgb)
+            .collect();
+        let code_surface = &[levels[1].1];
+
+        let primary = readable(plan.primary, tint / 2.0, hue, plan.step, &surfaces);
+        let secondary = readable(plan.secondary, tint, hue, plan.step, &surfaces);
+        let comment = readable(plan.secondary, tint, hue, plan.step, code_surface).0;
+        // Accents spread around the hue circle from the seed
+        let token = |offset: f64| {
+            readable(plan.accent, accent, (hue + offset) % 360.0, plan.step, code_surface).0
+        };
+
+        let file = ThemeFile {
+            label: "🎨 Custom".to_string(),
+            color_scheme: self.scheme,
+            order: i32::MAX,
+            surface: SurfaceColors {
+                background: format!(
+                    "linear-gradient(135deg, {}, {})",
+                    background[0].0, background[1].0
+                ),
+                levels: levels.iter().map(|(css, _)| css.clone()).collect(),
+            },
+            text: TextColors {
+                primary: primary.0.clone(),
+                secondary: secondary.0.clone(),
+                highlight: None,
+            },
+            brand: BrandColors {
+                color: Some(token(0.0)),
+            },
+            shadow: ShadowSettings {
+                color: hsl_channels(swatch(plan.shadow, tint, hue).1),
+                strength: plan.shadow_strength.to_string(),
+            },
+            syntax: SyntaxColors {
+                text: primary.0.clone(),
+                keyword: token(0.0),
+                string: token(140.0),
+                number: token(60.0),
+                comment,
+                function: token(280.0),
+                r#type: token(200.0),
+                operator: primary.0.clone(),
+                punctuation: secondary.0,
+                variable: token(240.0),
+                constant: token(30.0),
+                tag: token(330.0),
+                attribute: token(90.0),
+                namespace: token(170.0),
+            },
+        };
+        let mut theme =
+            Theme::from_file(CUSTOM, &file).expect("generated values are plain oklch() colors");
+        theme.stylesheet_query = format!("seed={}&", self.encode());
+        theme
+    }
+}
+
+/// Themes generated from recently used seeds, so pages and stylesheet
+/// requests don't run the contrast searches again for every request. The
+/// oldest seed is dropped once [`CACHED_THEMES`] are held.
+#[derive(Default)]
+pub struct GeneratedThemes {
+    cache: Mutex<ThemeCache>,
+}
+
+#[derive(Default)]
+struct ThemeCache {
+    themes: HashMap<Seed, Arc<Theme>>,
+    /// Seeds in the order they were generated, oldest first.
+    order: VecDeque<Seed>,
+}
+
+impl GeneratedThemes {
+    /// The theme for `seed`, generated on first use.
+    pub fn get(&self, seed: Seed) -> Arc<Theme> {
+        if let Some(theme) = self.lock().themes.get(&seed) {
+            return theme.clone();
+        }
+        // Generated outside the lock; a racing request at worst repeats it
+        let theme = Arc::new(seed.generate());
+        let mut cache = self.lock();
+        if !cache.themes.contains_key(&seed) {
+            if cache.order.len() >= CACHED_THEMES {
+                if let Some(oldest) = cache.order.pop_front() {
+                    cache.themes.remove(&oldest);
+                }
+            }
+            cache.order.push_back(seed);
+            cache.themes.insert(seed, theme.clone());
+        }
+        theme
+    }
+
+    /// Nothing is left half-updated while the lock is held, so a poisoned
+    /// cache is still usable.
+    fn lock(&self) -> MutexGuard<'_, ThemeCache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// `oklch()` CSS for the color, with chroma reduced until it fits sRGB so
+/// browsers draw exactly what was measured, and the sRGB color that CSS
+/// parses to.
+fn swatch(l: f64, c: f64, h: f64) -> (String, Rgb) {
+    let l = l.clamp(0.0, 1.0);
+    let in_gamut = |c: f64| Oklch { l, c, h }.in_gamut();
+    let c = if in_gamut(c) {
+        c
+    } else {
+        let (mut low, mut high) = (0.0, c);
+        for _ in 0..20 {
+            let mid = (low + high) / 2.0;
+            if in_gamut(mid) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    };
+    let css = format!("oklch({:.3} {:.3} {:.1})", l, (c * 1000.0).floor() / 1000.0, h);
+    let rgb = color::parse(&css).expect("formatted oklch() parses");
+    (css, rgb)
+}
+
+/// The first color from lightness `l` onwards, `step` at a time, that meets
+/// [`TARGET_CONTRAST`] on every background. White and black meet it on
+/// every planned surface, so the search always ends.
+fn readable(mut l: f64, c: f64, h: f64, step: f64, backgrounds: &[Rgb]) -> (String, Rgb) {
+    loop {
+        let (css, rgb) = swatch(l, c, h);
+        let readable = backgrounds
+            .iter()
+            .all(|background| rgb.contrast(background) >= TARGET_CONTRAST);
+        if readable || !(0.0..=1.0).contains(&(l + step)) {
+            return (css, rgb);
+        }
+        l += step;
+    }
+}
+
+/// `<hue> <saturation>% <lightness>%`, the format of `--surface-shadow`.
+fn hsl_channels(rgb: Rgb) -> String {
+    let max = rgb.r.max(rgb.g).max(rgb.b);
+    let min = rgb.r.min(rgb.g).min(rgb.b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    let (hue, saturation) = if delta == 0.0 {
+        (0.0, 0.0)
+    } else {
+        let hue = if max == rgb.r {
+            ((rgb.g - rgb.b) / delta).rem_euclid(6.0)
+        } else if max == rgb.g {
+            (rgb.b - rgb.r) / delta + 2.0
+        } else {
+            (rgb.r - rgb.g) / delta + 4.0
+        };
+        (hue * 60.0, delta / (1.0 - (2.0 * lightness - 1.0).abs()))
+    };
+    format!(
+        "{:.0} {:.0}% {:.0}%",
+        hue,
+        saturation * 100.0,
+        lightness * 100.0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contrast;
+
+    #[test]
+    fn seeds_round_trip() {
+        let seed = Seed::new(250, 40, ColorScheme::Dark);
+        assert_eq!(Seed::decode(&seed.encode()), Some(seed));
+        assert_eq!(Seed::decode("360-40-dark"), None);
+        assert_eq!(Seed::decode("250-40-dim"), None);
+    }
+
+    #[test]
+    fn generated_themes_are_cached_per_seed() {
+        let themes = GeneratedThemes::default();
+        let seed = Seed::new(250, 40, ColorScheme::Dark);
+        let first = themes.get(seed);
+        assert!(Arc::ptr_eq(&first, &themes.get(seed)));
+        assert_eq!(first.stylesheet(), seed.generate().stylesheet());
+
+        let other = themes.get(Seed::new(250, 40, ColorScheme::Light));
+        assert!(!Arc::ptr_eq(&first, &other));
+    }
+
+    #[test]
+    fn the_oldest_generated_theme_is_dropped() {
+        let themes = GeneratedThemes::default();
+        let first = themes.get(Seed::new(0, 0, ColorScheme::Dark));
+        for hue in 1..CACHED_THEMES as u16 {
+            themes.get(Seed::new(hue, 0, ColorScheme::Dark));
+        }
+        assert!(Arc::ptr_eq(
+            &first,
+            &themes.get(Seed::new(0, 0, ColorScheme::Dark))
+        ));
+
+        themes.get(Seed::new(0, 0, ColorScheme::Light));
+        assert_eq!(themes.lock().themes.len(), CACHED_THEMES);
+        assert!(!Arc::ptr_eq(
+            &first,
+            &themes.get(Seed::new(0, 0, ColorScheme::Dark))
+        ));
+    }
+
+    #[test]
+    fn generated_themes_meet_aa() {
+        let themes: Vec<Theme> = [ColorScheme::Light, ColorScheme::Dark]
+            .into_iter()
+            .flat_map(|scheme| {
+                (0..360).step_by(15).flat_map(move |hue| {
+                    [0, 50, 100].map(|vibrancy| Seed::new(hue, vibrancy, scheme).generate())
+                })
+            })
+            .collect();
+        let report = contrast::check_themes(&themes);
+        assert!(report.passed(), "\n{}", report);
+    }
+}
@@ -0,0 +1,628 @@
+pub mod generator;
+
+use anyhow::{bail, Context};
+use crate::settings::ThemeSettings;
+use crate::viewport::{self, Density};
+use config::{Config, File, FileFormat};
+use generator::CUSTOM;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Name of the theme that follows the visitor's OS light/dark preference.
+pub const AUTO: &str = "auto";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+}
+
+/// A theme as written in `config/themes/<name>.toml`. Values are CSS, usually
+/// Open Props variables like `var(--gray-3)`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    label: String,
+    color_scheme: ColorScheme,
+    #[serde(default)]
+    order: i32,
+    surface: SurfaceColors,
+    text: TextColors,
+    #[serde(default)]
+    brand: BrandColors,
+    shadow: ShadowSettings,
+    syntax: SyntaxColors,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SurfaceColors {
+    /// `--surface-0`, the page background.
+    background: String,
+    /// `--surface-1` to `--surface-4`.
+    levels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TextColors {
+    primary: String,
+    secondary: String,
+    highlight: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BrandColors {
+    color: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShadowSettings {
+    color: String,
+    strength: String,
+}
+
+/// One color per highlighter token class, see `static/styles.css`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SyntaxColors {
+    text: String,
+    keyword: String,
+    string: String,
+    number: String,
+    comment: String,
+    function: String,
+    r#type: String,
+    operator: String,
+    punctuation: String,
+    variable: String,
+    constant: String,
+    tag: String,
+    attribute: String,
+    namespace: String,
+}
+
+impl SyntaxColors {
+    fn tokens(&self) -> [(&'static str, &str); 14] {
+        [
+            ("text", &self.text),
+            ("keyword", &self.keyword),
+            ("string", &self.string),
+            ("number", &self.number),
+            ("comment", &self.comment),
+            ("function", &self.function),
+            ("type", &self.r#type),
+            ("operator", &self.operator),
+            ("punctuation", &self.punctuation),
+            ("variable", &self.variable),
+            ("constant", &self.constant),
+            ("tag", &self.tag),
+            ("attribute", &self.attribute),
+            ("namespace", &self.namespace),
+        ]
+    }
+}
+
+/// A validated theme with its stylesheet rendered once at startup.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// File stem, used in cookies, query strings and `$theme`.
+    pub name: String,
+    pub label: String,
+    pub color_scheme: ColorScheme,
+    order: i32,
+    /// `(--name, value)` for every custom property the theme sets.
+    variables: Vec<(String, String)>,
+    /// Variable declarations without the `:root` rule around them.
+    declarations: String,
+    stylesheet: String,
+    version: String,
+    /// Query parameters the stylesheet URL needs besides the version, e.g.
+    /// the seed of a generated theme.
+    stylesheet_query: String,
+}
+
+impl Theme {
+    fn from_file(name: &str, file: &ThemeFile) -> Result<Self, String> {
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err("theme file names may only use a-z, 0-9 and `-`".to_string());
+        }
+        if file.surface.levels.len() != 4 {
+            return Err(format!(
+                "`surface.levels` needs 4 colors (--surface-1 to --surface-4), got {}",
+                file.surface.levels.len()
+            ));
+        }
+
+        let mut variables: Vec<(String, &str)> = vec![
+            ("--surface-0".to_string(), &file.surface.background),
+            ("--text-1".to_string(), &file.text.primary),
+            ("--text-2".to_string(), &file.text.secondary),
+        ];
+        if let Some(highlight) = &file.text.highlight {
+            variables.push(("--text-highlight".to_string(), highlight));
+        }
+        for (level, color) in file.surface.levels.iter().enumerate() {
+            variables.push((format!("--surface-{}", level + 1), color));
+        }
+        if let Some(brand) = &file.brand.color {
+            variables.push(("--brand".to_string(), brand));
+        }
+        variables.push(("--surface-shadow".to_string(), &file.shadow.color));
+        variables.push(("--shadow-strength".to_string(), &file.shadow.strength));
+        variables.extend(
+            file.syntax
+                .tokens()
+                .into_iter()
+                .map(|(token, color)| (format!("--token-{token}"), color)),
+        );
+        let variables: Vec<(String, String)> = variables
+            .into_iter()
+            .map(|(name, value)| (name, value.trim().to_string()))
+            .collect();
+
+        let declarations = render_variables(
+            &format!("color-scheme: {};", file.color_scheme.as_str()),
+            &variables,
+        )?;
+        Ok(Self::with_stylesheet(
+            name,
+            &file.label,
+            file.color_scheme,
+            file.order,
+            format!(":root {{\n{declarations}\n}}\n"),
+            declarations,
+            variables,
+        ))
+    }
+
+    /// The `auto` theme: `light` on `:root`, overridden by `dark` for
+    /// visitors whose OS prefers a dark color scheme.
+    fn auto(light: &Theme, dark: &Theme) -> Self {
+        let stylesheet = format!(
+            ":root {{\n{}\n}}\n@media (prefers-color-scheme: dark) {{\n:root {{\n{}\n}}\n}}\n",
+            light.declarations, dark.declarations
+        );
+        Self::with_stylesheet(
+            AUTO,
+            "🖥️ Auto",
+            ColorScheme::Light,
+            i32::MIN,
+            stylesheet,
+            String::new(),
+            Vec::new(),
+        )
+    }
+
+    fn with_stylesheet(
+        name: &str,
+        label: &str,
+        color_scheme: ColorScheme,
+        order: i32,
+        stylesheet: String,
+        declarations: String,
+        variables: Vec<(String, String)>,
+    ) -> Self {
+        let version = content_version(&stylesheet);
+        Self {
+            name: name.to_string(),
+            label: label.to_string(),
+            color_scheme,
+            order,
+            variables,
+            declarations,
+            stylesheet,
+            version,
+            stylesheet_query: String::new(),
+        }
+    }
+
+    /// The custom properties the theme sets, as `(--name, value)`. Empty for
+    /// `auto`, which only combines other themes.
+    pub fn variables(&self) -> &[(String, String)] {
+        &self.variables
+    }
+
+    /// The variable declarations without a rule around them, for scoping the
+    /// theme to part of a page. Empty for `auto`.
+    pub fn declarations(&self) -> &str {
+        &self.declarations
+    }
+
+    /// Surface, text, brand, shadow and syntax token variables on `:root`,
+    /// served from `/theme/<name>.css`.
+    pub fn stylesheet(&self) -> &str {
+        &self.stylesheet
+    }
+
+    /// Content hash of [`Theme::stylesheet`], the base of its ETags.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The stylesheet served for `density`: the theme's variables, then the
+    /// `--density-*` variables of the tier, or media queries picking one.
+    pub fn stylesheet_for(&self, density: Option<Density>) -> String {
+        format!("{}{}", self.stylesheet, viewport::density_stylesheet(density))
+    }
+
+    /// Stylesheet URL for `<link>` tags. The version query changes with the
+    /// content, so responses to it can be cached for good.
+    pub fn stylesheet_url(&self, density: Option<Density>) -> String {
+        let density = density
+            .map(|density| format!("density={}&", density.as_str()))
+            .unwrap_or_default();
+        format!(
+            "/theme/{}.css?{}{}v={}",
+            self.name, self.stylesheet_query, density, self.version
+        )
+    }
+}
+
+/// The first 64 bits of the stylesheet's SHA-256, in hex. It only changes
+/// with the content, across restarts, builds and toolchains, so cached
+/// stylesheets stay valid through a deploy that leaves them alone.
+fn content_version(stylesheet: &str) -> String {
+    Sha256::digest(stylesheet.as_bytes())[..8]
+        .iter()
+        .fold(String::with_capacity(16), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// Renders `name: value;` lines, rejecting values that could end the
+/// declaration or the `<style>` element they are inlined into.
+fn render_variables(preamble: &str, variables: &[(String, String)]) -> Result<String, String> {
+    let mut css = preamble.to_string();
+    for (name, value) in variables {
+        if value.is_empty() {
+            return Err(format!("`{name}` is empty"));
+        }
+        if value.contains([';', '{', '}', '<', '>']) {
+            return Err(format!("`{name}` contains `;`, braces or angle brackets: {value}"));
+        }
+        if !css.is_empty() {
+            css.push('\n');
+        }
+        let _ = write!(css, "{name}: {value};");
+    }
+    Ok(css)
+}
+
+/// Every theme under `config/themes`, loaded and validated at startup, plus
+/// the `auto` theme built from the `light` and `dark` ones.
+pub struct ThemeRegistry {
+    themes: Vec<Theme>,
+    auto: Theme,
+    default: String,
+    light: usize,
+    dark: usize,
+}
+
+impl ThemeRegistry {
+    /// Loads `*.toml` from `dir`. Every problem in every file is reported in
+    /// one error so a broken theme set can be fixed in one go.
+    pub fn load(dir: &Path, settings: &ThemeSettings) -> anyhow::Result<Self> {
+        let entries = std::fs::read_dir(dir)
+            .with_context(|| format!("Cannot read theme directory {}", dir.display()))?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        let mut themes: Vec<Theme> = Vec::new();
+        let mut problems: Vec<String> = Vec::new();
+        for path in &paths {
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default();
+            if name == AUTO || name == CUSTOM {
+                problems.push(format!(
+                    "{}: `{AUTO}` and `{CUSTOM}` are reserved for the OS-preference and generated themes",
+                    path.display()
+                ));
+                continue;
+            }
+            let parsed = Config::builder()
+                .add_source(File::from(path.as_path()).format(FileFormat::Toml))
+                .build()
+                .and_then(|config| config.try_deserialize::<ThemeFile>())
+                .map_err(|e| e.to_string())
+                .and_then(|file| Theme::from_file(name, &file));
+            match parsed {
+                Ok(theme) => themes.push(theme),
+                Err(problem) => problems.push(format!("{}: {}", path.display(), problem)),
+            }
+        }
+        if !problems.is_empty() {
+            bail!("Invalid themes:\n  {}", problems.join("\n  "));
+        }
+
+        themes.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.name.cmp(&b.name)));
+        let scheme_theme = |setting: &str, name: &str, scheme: ColorScheme| {
+            match themes.iter().position(|theme| theme.name == name) {
+                Some(index) if themes[index].color_scheme == scheme => Ok(index),
+                Some(_) => Err(format!(
+                    "`themes.{setting}` must name a theme with color_scheme = \"{}\", `{name}` is not one",
+                    scheme.as_str()
+                )),
+                None => Err(format!("`themes.{setting}` names an unknown theme `{name}`")),
+            }
+        };
+        let light = scheme_theme("light", &settings.light, ColorScheme::Light);
+        let dark = scheme_theme("dark", &settings.dark, ColorScheme::Dark);
+        if settings.default != AUTO && !themes.iter().any(|theme| theme.name == settings.default) {
+            problems.push(format!(
+                "`themes.default` must be `{AUTO}` or a theme in {}, not `{}`",
+                dir.display(),
+                settings.default
+            ));
+        }
+        let (light, dark) = match (light, dark) {
+            (Ok(light), Ok(dark)) if problems.is_empty() => (light, dark),
+            (light, dark) => {
+                problems.extend(light.err());
+                problems.extend(dark.err());
+                bail!("Invalid theme settings:\n  {}", problems.join("\n  "));
+            }
+        };
+
+        Ok(Self {
+            auto: Theme::auto(&themes[light], &themes[dark]),
+            themes,
+            default: settings.default.clone(),
+            light,
+            dark,
+        })
+    }
+
+    /// Themes in display order (`order`, then name), without `auto`.
+    pub fn all(&self) -> &[Theme] {
+        &self.themes
+    }
+
+    /// A theme by name, including `auto`.
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        let name = name.to_lowercase();
+        if name == AUTO {
+            return Some(&self.auto);
+        }
+        self.themes.iter().find(|theme| theme.name == name)
+    }
+
+    pub fn auto(&self) -> &Theme {
+        &self.auto
+    }
+
+    /// The canonical name of a chosen theme, or the configured default for
+    /// no or unknown choice. This is what `$theme` and the cookie hold.
+    pub fn preference(&self, choice: Option<&str>) -> &str {
+        choice
+            .and_then(|name| self.get(name))
+            .map_or(self.default.as_str(), |theme| theme.name.as_str())
+    }
+
+    /// The theme to render for `preference`. `auto` follows the
+    /// `Sec-CH-Prefers-Color-Scheme` hint when the browser sent one, and
+    /// otherwise leaves it to the auto stylesheet's media query.
+    pub fn pick(&self, preference: &str, hint: Option<ColorScheme>) -> &Theme {
+        match self.get(preference) {
+            Some(theme) if theme.name != AUTO => theme,
+            _ => match hint {
+                Some(ColorScheme::Light) => &self.themes[self.light],
+                Some(ColorScheme::Dark) => &self.themes[self.dark],
+                None => &self.auto,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const LIGHT: &str = r#"
+label = "Light"
+color_scheme = "light"
+
+[surface]
+background = "var(--gray-0)"
+levels = ["var(--gray-1)", "var(--gray-2)", "var(--gray-3)", "var(--gray-4)"]
+
+[text]
+primary = "var(--gray-12)"
+secondary = "var(--gray-9)"
+
+[shadow]
+color = "220 3% 15%"
+strength = "1%"
+
+[syntax]
+text = "var(--gray-12)"
+keyword = "var(--red-9)"
+string = "var(--blue-9)"
+number = "var(--orange-9)"
+comment = "var(--gray-9)"
+function = "var(--purple-9)"
+type = "var(--orange-9)"
+operator = "var(--gray-12)"
+punctuation = "var(--gray-9)"
+variable = "var(--gray-12)"
+constant = "var(--blue-9)"
+tag = "var(--red-9)"
+attribute = "var(--blue-9)"
+namespace = "var(--purple-9)"
+"#;
+
+    fn dark() -> String {
+        LIGHT
+            .replace(r#"label = "Light""#, r#"label = "Dark""#)
+            .replace(r#"color_scheme = "light""#, r#"color_scheme = "dark""#)
+    }
+
+    fn settings(default: &str) -> ThemeSettings {
+        ThemeSettings {
+            default: default.to_string(),
+            light: "light".to_string(),
+            dark: "dark".to_string(),
+        }
+    }
+
+    /// A fresh theme directory under the system temp dir holding `files`.
+    fn fixture(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("web-app-themes-{}-{test}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in files {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+        dir
+    }
+
+    fn load_error(test: &str, files: &[(&str, &str)], default: &str) -> String {
+        let dir = fixture(test, files);
+        let result = ThemeRegistry::load(&dir, &settings(default));
+        std::fs::remove_dir_all(&dir).unwrap();
+        format!("{:#}", result.err().expect("themes should not load"))
+    }
+
+    #[test]
+    fn valid_themes_load() {
+        let dark = dark();
+        let dir = fixture("valid", &[("light.toml", LIGHT), ("dark.toml", &dark)]);
+        let themes = ThemeRegistry::load(&dir, &settings(AUTO)).expect("themes load");
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(themes.all().len(), 2);
+        assert!(themes
+            .auto()
+            .stylesheet()
+            .contains("@media (prefers-color-scheme: dark)"));
+    }
+
+    #[test]
+    fn missing_required_token_is_rejected() {
+        let dark = dark().replace("namespace = \"var(--purple-9)\"\n", "");
+        let error = load_error(
+            "missing",
+            &[("light.toml", LIGHT), ("dark.toml", &dark)],
+            AUTO,
+        );
+        assert!(error.contains("dark.toml"), "{error}");
+        assert!(error.contains("namespace"), "{error}");
+    }
+
+    #[test]
+    fn reserved_theme_names_are_rejected() {
+        let dark = dark();
+        let error = load_error(
+            "reserved",
+            &[
+                ("light.toml", LIGHT),
+                ("dark.toml", &dark),
+                ("auto.toml", LIGHT),
+            ],
+            AUTO,
+        );
+        assert!(error.contains("auto.toml"), "{error}");
+        assert!(error.contains("reserved"), "{error}");
+    }
+
+    #[test]
+    fn bad_toml_is_rejected() {
+        let error = load_error(
+            "bad-toml",
+            &[("light.toml", LIGHT), ("dark.toml", "label = \"Dark\n")],
+            AUTO,
+        );
+        assert!(error.contains("dark.toml"), "{error}");
+    }
+
+    #[test]
+    fn unknown_default_is_rejected() {
+        let dark = dark();
+        let error = load_error(
+            "default",
+            &[("light.toml", LIGHT), ("dark.toml", &dark)],
+            "sepia",
+        );
+        assert!(error.contains("`themes.default`"), "{error}");
+        assert!(error.contains("`sepia`"), "{error}");
+    }
+
+    fn registry(test: &str, default: &str) -> ThemeRegistry {
+        let dark = dark();
+        let dir = fixture(test, &[("light.toml", LIGHT), ("dark.toml", &dark)]);
+        let themes = ThemeRegistry::load(&dir, &settings(default)).expect("themes load");
+        std::fs::remove_dir_all(&dir).unwrap();
+        themes
+    }
+
+    #[test]
+    fn preference_falls_back_to_the_default() {
+        let themes = registry("preference", "dark");
+        assert_eq!(themes.preference(Some("light")), "light");
+        assert_eq!(themes.preference(Some("LIGHT")), "light");
+        assert_eq!(themes.preference(Some(AUTO)), AUTO);
+        assert_eq!(themes.preference(Some("sepia")), "dark");
+        assert_eq!(themes.preference(None), "dark");
+    }
+
+    #[test]
+    fn chosen_theme_overrides_the_hint() {
+        let themes = registry("chosen", AUTO);
+        assert_eq!(themes.pick("dark", Some(ColorScheme::Light)).name, "dark");
+        assert_eq!(themes.pick("light", Some(ColorScheme::Dark)).name, "light");
+        assert_eq!(themes.pick("light", None).name, "light");
+    }
+
+    #[test]
+    fn auto_follows_the_hint() {
+        let themes = registry("hint", AUTO);
+        assert_eq!(themes.pick(AUTO, Some(ColorScheme::Dark)).name, "dark");
+        assert_eq!(themes.pick(AUTO, Some(ColorScheme::Light)).name, "light");
+    }
+
+    #[test]
+    fn auto_without_a_hint_uses_the_media_query() {
+        let themes = registry("media-query", AUTO);
+        let theme = themes.pick(AUTO, None);
+        assert_eq!(theme.name, AUTO);
+        assert!(theme
+            .stylesheet()
+            .contains("@media (prefers-color-scheme: dark)"));
+        // Unknown choices are `auto` when that is the default
+        assert_eq!(
+            themes.pick(themes.preference(Some("sepia")), None).name,
+            AUTO
+        );
+    }
+
+    #[test]
+    fn versions_are_content_hashes() {
+        // SHA-256 of "abc" is ba7816bf8f01cfea414140de5dae2223...
+        assert_eq!(content_version("abc"), "ba7816bf8f01cfea");
+        assert_eq!(content_version(""), "e3b0c44298fc1c14");
+    }
+}
@@ -0,0 +1,240 @@
+use crate::client_hints::{DPR, VIEWPORT_WIDTH};
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{request::Parts, HeaderMap, Method},
+};
+use serde::Deserialize;
+use std::convert::Infallible;
+
+/// Widths below this many CSS pixels are [`Density::Compact`]: 48rem, where
+/// `static/styles.css` drops to one column.
+const COMPACT_BELOW: f64 = 768.0;
+/// Widths from this many CSS pixels on are [`Density::Spacious`] (80rem).
+const SPACIOUS_FROM: f64 = 1280.0;
+/// Only phones have a DPR this high, and they stay compact in landscape.
+const HANDHELD_DPR: f64 = 3.0;
+/// Widest landscape phone layout, in CSS pixels.
+const HANDHELD_BELOW: f64 = 1024.0;
+
+/// How much room spacing and corners take, picked per viewport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+    Compact,
+    Comfortable,
+    Spacious,
+}
+
+impl Density {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Density::Compact => "compact",
+            Density::Comfortable => "comfortable",
+            Density::Spacious => "spacious",
+        }
+    }
+
+    /// `--density-*` declarations, Open Props sizes used for gaps, padding
+    /// and corner radii (see `static/styles.css`).
+    fn declarations(&self) -> &'static str {
+        match self {
+            Density::Compact => {
+                "--density-gap: var(--size-3);\n--density-padding: var(--size-3);\n--density-radius: var(--radius-2);"
+            }
+            Density::Comfortable => {
+                "--density-gap: var(--size-4);\n--density-padding: var(--size-fluid-3);\n--density-radius: var(--radius-3);"
+            }
+            Density::Spacious => {
+                "--density-gap: var(--size-6);\n--density-padding: var(--size-7);\n--density-radius: var(--radius-3);"
+            }
+        }
+    }
+}
+
+/// CSS for a density tier. Without one, comfortable on `:root` with media
+/// queries for the compact and spacious widths.
+pub fn density_stylesheet(density: Option<Density>) -> String {
+    match density {
+        Some(density) => format!(":root {{\n{}\n}}\n", density.declarations()),
+        None => format!(
+            ":root {{\n{}\n}}\n@media (max-width: {}px) {{\n:root {{\n{}\n}}\n}}\n@media (min-width: {}px) {{\n:root {{\n{}\n}}\n}}\n",
+            Density::Comfortable.declarations(),
+            COMPACT_BELOW - 0.02,
+            Density::Compact.declarations(),
+            SPACIOUS_FROM,
+            Density::Spacious.declarations()
+        ),
+    }
+}
+
+/// `$viewport`, which pages may send with Datastar requests. It is current
+/// where the hints are as of the last navigation, so it wins over them.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct ViewportSignal {
+    pub width: Option<f64>,
+    pub dpr: Option<f64>,
+}
+
+/// What the server knows about the visitor's viewport, from the
+/// `Sec-CH-Viewport-Width` and `Sec-CH-DPR` client hints and the `$viewport`
+/// signal. Both are optional, so every field may be unknown.
+///
+/// As an extractor it reads the hints and, for `GET` Datastar actions, the
+/// signal from the query. Handlers receiving signals in a body combine them
+/// with [`ViewportContext::with_signal`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ViewportContext {
+    /// Layout viewport width in CSS pixels.
+    pub width: Option<f64>,
+    /// Device pixel ratio.
+    pub dpr: Option<f64>,
+}
+
+impl ViewportContext {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let number = |name: &str| {
+            headers
+                .get(name)?
+                .to_str()
+                .ok()?
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite() && *value > 0.0)
+        };
+        Self {
+            width: number(VIEWPORT_WIDTH),
+            dpr: number(DPR),
+        }
+    }
+
+    /// The context with the signal's values over the hints'.
+    pub fn with_signal(self, signal: Option<ViewportSignal>) -> Self {
+        let Some(signal) = signal else {
+            return self;
+        };
+        let valid = |value: Option<f64>| value.filter(|v| v.is_finite() && *v > 0.0);
+        Self {
+            width: valid(signal.width).or(self.width),
+            dpr: valid(signal.dpr).or(self.dpr),
+        }
+    }
+
+    /// The density tier for the width, or `None` when the width is unknown
+    /// and CSS has to decide. A phone-class DPR keeps landscape phones
+    /// compact.
+    pub fn density(&self) -> Option<Density> {
+        let width = self.width?;
+        let handheld = self.dpr.is_some_and(|dpr| dpr >= HANDHELD_DPR) && width < HANDHELD_BELOW;
+        Some(if width < COMPACT_BELOW || handheld {
+            Density::Compact
+        } else if width < SPACIOUS_FROM {
+            Density::Comfortable
+        } else {
+            Density::Spacious
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct DatastarQuery {
+    datastar: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ViewportSignals {
+    viewport: Option<ViewportSignal>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ViewportContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let hints = Self::from_headers(&parts.headers);
+        if !matches!(parts.method, Method::GET | Method::HEAD) {
+            return Ok(hints);
+        }
+        // The signal is optional, so a missing or malformed one is ignored
+        let signal = Query::<DatastarQuery>::try_from_uri(&parts.uri)
+            .ok()
+            .and_then(|Query(query)| query.datastar)
+            .and_then(|payload| serde_json::from_str::<ViewportSignals>(&payload).ok())
+            .and_then(|signals| signals.viewport);
+        Ok(hints.with_signal(signal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn density(width: f64, dpr: Option<f64>) -> Option<Density> {
+        ViewportContext {
+            width: Some(width),
+            dpr,
+        }
+        .density()
+    }
+
+    fn hints(width: &str, dpr: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(VIEWPORT_WIDTH, width.parse().unwrap());
+        headers.insert(DPR, dpr.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn tiers_change_at_the_breakpoints() {
+        assert_eq!(density(320.0, None), Some(Density::Compact));
+        assert_eq!(density(767.0, None), Some(Density::Compact));
+        assert_eq!(density(768.0, None), Some(Density::Comfortable));
+        assert_eq!(density(1279.0, None), Some(Density::Comfortable));
+        assert_eq!(density(1280.0, None), Some(Density::Spacious));
+        assert_eq!(ViewportContext::default().density(), None);
+    }
+
+    #[test]
+    fn high_dpr_phones_stay_compact_in_landscape() {
+        assert_eq!(density(900.0, Some(3.0)), Some(Density::Compact));
+        assert_eq!(density(1023.0, Some(3.5)), Some(Density::Compact));
+        assert_eq!(density(1024.0, Some(3.0)), Some(Density::Comfortable));
+        assert_eq!(density(900.0, Some(2.0)), Some(Density::Comfortable));
+    }
+
+    #[test]
+    fn signals_win_over_hints() {
+        let viewport = ViewportContext::from_headers(&hints("390", "3"));
+        assert_eq!(viewport.density(), Some(Density::Compact));
+
+        let resized = viewport.with_signal(Some(ViewportSignal {
+            width: Some(1440.0),
+            dpr: None,
+        }));
+        assert_eq!(resized.width, Some(1440.0));
+        assert_eq!(resized.dpr, Some(3.0));
+        assert_eq!(resized.density(), Some(Density::Spacious));
+
+        // Unusable signal values leave the hints alone
+        let invalid = viewport.with_signal(Some(ViewportSignal {
+            width: Some(0.0),
+            dpr: Some(f64::NAN),
+        }));
+        assert_eq!(invalid, viewport);
+        assert_eq!(viewport.with_signal(None), viewport);
+    }
+
+    #[test]
+    fn malformed_hints_are_unknown() {
+        let viewport = ViewportContext::from_headers(&hints("wide", "-1"));
+        assert_eq!(viewport, ViewportContext::default());
+    }
+
+    #[test]
+    fn without_a_tier_media_queries_decide() {
+        let css = density_stylesheet(None);
+        assert!(css.contains("@media (max-width: 767.98px)"));
+        assert!(css.contains("@media (min-width: 1280px)"));
+        assert!(!density_stylesheet(Some(Density::Compact)).contains("@media"));
+    }
+}